@@ -0,0 +1,213 @@
+use std::{
+    collections::HashSet,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use rusqlite::{Connection, OpenFlags};
+use serde_json::Value;
+
+use crate::config::{ActionKind, ListItem};
+
+/// where firefox and its forks keep their profiles, relative to the home directory.
+const FIREFOX_DIRS: &[&str] = &[
+    ".mozilla/firefox",
+    ".var/app/org.mozilla.firefox/.mozilla/firefox",
+    "snap/firefox/common/.mozilla/firefox",
+    ".librewolf",
+];
+
+/// where chromium-based browsers keep their profiles, relative to the config directory.
+const CHROMIUM_DIRS: &[&str] = &[
+    "chromium",
+    "google-chrome",
+    "BraveSoftware/Brave-Browser",
+    "vivaldi",
+    "microsoft-edge",
+];
+
+/// a bookmark or history entry.
+struct Page {
+    title: String,
+    url: String,
+}
+
+/// the bookmarks of every firefox and chromium profile, and up to `history` of the most
+/// visited pages from each. each item opens its url in the default browser.
+pub fn bookmarks(history: usize) -> Vec<ListItem> {
+    let mut pages = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        for dir in FIREFOX_DIRS {
+            for profile in profiles(&home.join(dir), "places.sqlite") {
+                firefox(&profile, history, &mut pages);
+            }
+        }
+    }
+    if let Some(config) = dirs::config_dir() {
+        for dir in CHROMIUM_DIRS {
+            for profile in profiles(&config.join(dir), "Bookmarks") {
+                chromium(&profile, history, &mut pages);
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    pages
+        .into_iter()
+        // bookmarklets and browser-internal queries only mean something inside the browser.
+        .filter(|page| !page.url.starts_with("javascript:") && !page.url.starts_with("place:"))
+        .filter(|page| seen.insert(page.url.clone()))
+        .map(|page| ListItem {
+            search: Some(format!("{} {}", page.title, page.url)),
+            name: if page.title.is_empty() {
+                page.url.clone()
+            } else {
+                page.title
+            },
+            action: ActionKind::OpenUrl(page.url.clone()).into(),
+            description: Some(page.url),
+            variables: Vec::new(),
+        })
+        .collect()
+}
+
+/// the profile directories in `dir` that contain `file`.
+fn profiles(dir: &Path, file: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut profiles: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.join(file).is_file())
+        .collect();
+    profiles.sort();
+    profiles
+}
+
+fn firefox(profile: &Path, history: usize, pages: &mut Vec<Page>) {
+    let result = query_copy(&profile.join("places.sqlite"), |db| {
+        let mut found = query(
+            db,
+            "SELECT b.title, p.url FROM moz_bookmarks b JOIN moz_places p ON b.fk = p.id \
+             WHERE b.type = 1 AND p.url NOT LIKE 'place:%' ORDER BY b.lastModified DESC",
+            0,
+        )?;
+        if history > 0 {
+            found.extend(query(
+                db,
+                "SELECT title, url FROM moz_places \
+                 WHERE last_visit_date IS NOT NULL AND hidden = 0 \
+                 ORDER BY frecency DESC LIMIT ?1",
+                history,
+            )?);
+        }
+        Ok(found)
+    });
+    match result {
+        Ok(found) => pages.extend(found),
+        Err(e) => eprintln!(
+            "failed to read firefox bookmarks from {}: {e}",
+            profile.display()
+        ),
+    }
+}
+
+fn chromium(profile: &Path, history: usize, pages: &mut Vec<Page>) {
+    let path = profile.join("Bookmarks");
+    match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str::<Value>(&text).map_err(|e| e.to_string()))
+    {
+        Ok(json) => {
+            if let Some(roots) = json["roots"].as_object() {
+                for root in roots.values() {
+                    chromium_folder(root, pages);
+                }
+            }
+        }
+        Err(e) => eprintln!("failed to read {}: {e}", path.display()),
+    }
+
+    let path = profile.join("History");
+    if history > 0 && path.is_file() {
+        let result = query_copy(&path, |db| {
+            query(
+                db,
+                "SELECT title, url FROM urls WHERE hidden = 0 \
+                 ORDER BY visit_count DESC LIMIT ?1",
+                history,
+            )
+        });
+        match result {
+            Ok(found) => pages.extend(found),
+            Err(e) => eprintln!("failed to read {}: {e}", path.display()),
+        }
+    }
+}
+
+fn chromium_folder(node: &Value, pages: &mut Vec<Page>) {
+    match node["type"].as_str() {
+        Some("url") => {
+            if let Some(url) = node["url"].as_str() {
+                pages.push(Page {
+                    title: node["name"].as_str().unwrap_or_default().to_string(),
+                    url: url.to_string(),
+                });
+            }
+        }
+        _ => {
+            for child in node["children"].as_array().into_iter().flatten() {
+                chromium_folder(child, pages);
+            }
+        }
+    }
+}
+
+/// runs `f` on a copy of the database at `path`. browsers keep their databases locked while
+/// they're open, so they can't be read in place.
+fn query_copy(
+    path: &Path,
+    f: impl FnOnce(&Connection) -> rusqlite::Result<Vec<Page>>,
+) -> Result<Vec<Page>, String> {
+    // a new directory only we can read, since the copy holds the user's history. it's removed,
+    // without following links, when it's dropped.
+    let dir = tempfile::Builder::new()
+        .prefix("superspace-")
+        .permissions(fs::Permissions::from_mode(0o700))
+        .tempdir_in(dirs::runtime_dir().unwrap_or_else(std::env::temp_dir))
+        .map_err(|e| e.to_string())?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let copy = dir.path().join(&*name);
+    fs::copy(path, &copy)
+        .map_err(|e| e.to_string())
+        .and_then(|_| {
+            // recent changes may still be in the write-ahead log, which needs a writable
+            // connection to be read.
+            let _ = fs::copy(
+                path.with_file_name(format!("{name}-wal")),
+                dir.path().join(format!("{name}-wal")),
+            );
+            Connection::open_with_flags(&copy, OpenFlags::SQLITE_OPEN_READ_WRITE)
+                .and_then(|db| f(&db))
+                .map_err(|e| e.to_string())
+        })
+}
+
+fn query(db: &Connection, sql: &str, limit: usize) -> rusqlite::Result<Vec<Page>> {
+    let mut statement = db.prepare(sql)?;
+    let rows = if statement.parameter_count() > 0 {
+        statement.query_map([limit as i64], page)?
+    } else {
+        statement.query_map([], page)?
+    };
+    rows.collect()
+}
+
+fn page(row: &rusqlite::Row) -> rusqlite::Result<Page> {
+    Ok(Page {
+        title: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+        url: row.get(1)?,
+    })
+}
@@ -0,0 +1,442 @@
+use std::{f64::consts, iter::Peekable, str::Chars};
+
+/// evaluates an arithmetic expression, like `2 * (3 + 4)`, `sqrt(2)^2` or `0xff + 0b1`.
+pub fn evaluate(expression: &str) -> Result<f64, String> {
+    let mut parser = Parser {
+        tokens: tokenize(expression)?.into_iter().peekable(),
+    };
+    let value = parser.expression()?;
+    match parser.tokens.next() {
+        None => Ok(value),
+        Some(token) => Err(format!("unexpected {token}.")),
+    }
+}
+
+/// formats a result without float noise, e.g. `0.1 + 0.2` is shown as `0.3`.
+pub fn format_number(value: f64) -> String {
+    if value.is_nan() {
+        String::from("NaN")
+    } else if value == 0.0 {
+        String::from("0") // not -0.
+    } else if value.is_infinite() {
+        String::from(if value > 0.0 { "infinity" } else { "-infinity" })
+    } else if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{value:.0}")
+    } else if value.abs() >= 1e15 || value.abs() < 1e-6 {
+        let formatted = format!("{value:.11e}");
+        let (mantissa, exponent) = formatted.split_once('e').expect("exponent format has an e");
+        format!("{}e{exponent}", trim_zeros(mantissa))
+    } else {
+        // 12 significant digits is enough to hide rounding errors.
+        let decimals = (11 - value.abs().log10().floor() as i32).max(0) as usize;
+        trim_zeros(&format!("{value:.decimals$}")).to_string()
+    }
+}
+
+fn trim_zeros(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "number '{}'", format_number(*n)),
+            Token::Ident(name) => write!(f, "'{name}'"),
+            Token::Op(c) => write!(f, "'{c}'"),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            tokens.push(Token::Number(number(&mut chars)?));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(name.to_lowercase()));
+        } else if c == '*' {
+            chars.next();
+            // `**` is another way of writing `^`.
+            if chars.next_if_eq(&'*').is_some() {
+                tokens.push(Token::Op('^'));
+            } else {
+                tokens.push(Token::Op('*'));
+            }
+        } else if "+-/%^(),!".contains(c) {
+            chars.next();
+            tokens.push(Token::Op(c));
+        } else if c == '×' || c == '÷' || c == '−' {
+            chars.next();
+            tokens.push(Token::Op(match c {
+                '×' => '*',
+                '÷' => '/',
+                _ => '-',
+            }));
+        } else {
+            return Err(format!("unexpected character '{c}'."));
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn number(chars: &mut Peekable<Chars>) -> Result<f64, String> {
+    let mut digits = String::new();
+    if chars.next_if_eq(&'0').is_some() {
+        let radix = match chars.peek() {
+            Some('x' | 'X') => 16,
+            Some('b' | 'B') => 2,
+            Some('o' | 'O') => 8,
+            _ => 10,
+        };
+        if radix != 10 {
+            chars.next();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                if c != '_' {
+                    digits.push(c);
+                }
+            }
+            return u64::from_str_radix(&digits, radix)
+                .map(|n| n as f64)
+                .map_err(|_| format!("invalid base {radix} number '{digits}'."));
+        }
+        digits.push('0');
+    }
+
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.' || *c == '_') {
+        if c != '_' {
+            digits.push(c);
+        }
+    }
+    // exponents, like 1e3 or 2.5e-4. otherwise, `2e` is 2 * e.
+    let mut ahead = chars.clone();
+    if ahead.next_if(|c| *c == 'e' || *c == 'E').is_some() {
+        ahead.next_if(|c| *c == '-' || *c == '+');
+        if ahead.peek().is_some_and(char::is_ascii_digit) {
+            chars.next();
+            digits.push('e');
+            digits.extend(chars.next_if(|c| *c == '-' || *c == '+'));
+            while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                digits.push(c);
+            }
+        }
+    }
+
+    digits
+        .parse()
+        .map_err(|_| format!("invalid number '{digits}'."))
+}
+
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn eat(&mut self, op: char) -> bool {
+        self.tokens.next_if_eq(&Token::Op(op)).is_some()
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), String> {
+        match self.tokens.next() {
+            Some(Token::Op(c)) if c == op => Ok(()),
+            Some(token) => Err(format!("expected '{op}', found {token}.")),
+            None => Err(format!("expected '{op}'.")),
+        }
+    }
+
+    /// expression = term (("+" | "-") term)*
+    fn expression(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// term = unary (("*" | "/" | "%" | implicit) unary)*
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                value /= self.unary()?;
+            } else if self.eat('%') {
+                value = value.rem_euclid(self.unary()?);
+            } else if matches!(
+                self.tokens.peek(),
+                Some(Token::Number(_) | Token::Ident(_) | Token::Op('('))
+            ) {
+                // implicit multiplication, like `2pi` or `3(1 + 2)`.
+                value *= self.unary()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// unary = ("-" | "+") unary | power
+    fn unary(&mut self) -> Result<f64, String> {
+        if self.eat('-') {
+            Ok(-self.unary()?)
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    /// power = postfix ("^" unary)?
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.postfix()?;
+        if self.eat('^') {
+            Ok(base.powf(self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    /// postfix = primary "!"*
+    fn postfix(&mut self) -> Result<f64, String> {
+        let mut value = self.primary()?;
+        while self.eat('!') {
+            value = factorial(value)?;
+        }
+        Ok(value)
+    }
+
+    /// primary = number | constant | function "(" arguments ")" | "(" expression ")"
+    fn primary(&mut self) -> Result<f64, String> {
+        match self.tokens.next() {
+            Some(Token::Number(n)) => Ok(n),
+            Some(Token::Op('(')) => {
+                let value = self.expression()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if self.eat('(') {
+                    let mut arguments = Vec::new();
+                    if !self.eat(')') {
+                        loop {
+                            arguments.push(self.expression()?);
+                            if self.eat(')') {
+                                break;
+                            }
+                            self.expect(',')?;
+                        }
+                    }
+                    call(&name, &arguments)
+                } else {
+                    constant(&name).ok_or_else(|| format!("unknown constant '{name}'."))
+                }
+            }
+            Some(token) => Err(format!("unexpected {token}.")),
+            None => Err(String::from("unexpected end of expression.")),
+        }
+    }
+}
+
+fn constant(name: &str) -> Option<f64> {
+    Some(match name {
+        "pi" | "π" => consts::PI,
+        "tau" | "τ" => consts::TAU,
+        "e" => consts::E,
+        "phi" | "φ" => 1.618_033_988_749_895,
+        "inf" | "infinity" => f64::INFINITY,
+        _ => return None,
+    })
+}
+
+fn call(name: &str, arguments: &[f64]) -> Result<f64, String> {
+    let unary = |f: fn(f64) -> f64| match arguments {
+        [x] => Ok(f(*x)),
+        _ => Err(format!("{name} takes 1 argument.")),
+    };
+    let binary = |f: fn(f64, f64) -> f64| match arguments {
+        [x, y] => Ok(f(*x, *y)),
+        _ => Err(format!("{name} takes 2 arguments.")),
+    };
+
+    match name {
+        "sqrt" => unary(f64::sqrt),
+        "cbrt" => unary(f64::cbrt),
+        "abs" => unary(f64::abs),
+        "floor" => unary(f64::floor),
+        "ceil" => unary(f64::ceil),
+        "round" => unary(f64::round),
+        "trunc" => unary(f64::trunc),
+        "sign" => unary(f64::signum),
+        "exp" => unary(f64::exp),
+        "ln" => unary(f64::ln),
+        "log" => match arguments {
+            [x] => Ok(x.log10()),
+            [x, base] => Ok(x.log(*base)),
+            _ => Err(String::from("log takes 1 or 2 arguments.")),
+        },
+        "log2" => unary(f64::log2),
+        "log10" => unary(f64::log10),
+        "sin" => unary(f64::sin),
+        "cos" => unary(f64::cos),
+        "tan" => unary(f64::tan),
+        "asin" => unary(f64::asin),
+        "acos" => unary(f64::acos),
+        "atan" => unary(f64::atan),
+        "atan2" => binary(f64::atan2),
+        "sinh" => unary(f64::sinh),
+        "cosh" => unary(f64::cosh),
+        "tanh" => unary(f64::tanh),
+        "deg" => unary(f64::to_degrees),
+        "rad" => unary(f64::to_radians),
+        "pow" => binary(f64::powf),
+        "hypot" => binary(f64::hypot),
+        "fact" => match arguments {
+            [x] => factorial(*x),
+            _ => Err(String::from("fact takes 1 argument.")),
+        },
+        "min" | "max" if arguments.is_empty() => Err(format!("{name} takes at least 1 argument.")),
+        "min" => Ok(arguments.iter().copied().fold(f64::INFINITY, f64::min)),
+        "max" => Ok(arguments.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        _ => Err(format!("unknown function '{name}'.")),
+    }
+}
+
+fn factorial(n: f64) -> Result<f64, String> {
+    if n < 0.0 || n.fract() != 0.0 {
+        return Err(String::from(
+            "factorials are only defined for whole numbers.",
+        ));
+    }
+    // anything past 170! overflows anyway.
+    Ok((1..=n.min(171.0) as u32).map(f64::from).product())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str) -> String {
+        format_number(evaluate(expression).unwrap())
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3"), "7");
+        assert_eq!(eval("(1 + 2) * 3"), "9");
+        assert_eq!(eval("10 - 4 - 3"), "3");
+        assert_eq!(eval("12 / 3 / 2"), "2");
+        assert_eq!(eval("7 % 4 + 1"), "4");
+        assert_eq!(eval("2pi / pi"), "2");
+        assert_eq!(eval("3(1 + 2)"), "9");
+    }
+
+    #[test]
+    fn powers() {
+        assert_eq!(eval("2 ^ 3 ^ 2"), "512");
+        assert_eq!(eval("2 ** 10"), "1024");
+        assert_eq!(eval("-2 ^ 2"), "-4");
+        assert_eq!(eval("2 ^ -1"), "0.5");
+        assert_eq!(eval("3!"), "6");
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-3"), "-3");
+        assert_eq!(eval("--3"), "3");
+        assert_eq!(eval("2 * -3"), "-6");
+        assert_eq!(eval("-(1 + 2)"), "-3");
+        assert_eq!(eval("-0"), "0");
+        assert_eq!(eval("0 * -1"), "0");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(eval("0.1 + 0.2"), "0.3");
+        assert_eq!(eval("0xff + 0b1 + 0o10"), "264");
+        assert_eq!(eval("1_000 * 2"), "2000");
+        assert_eq!(eval("1e3"), "1000");
+        assert_eq!(eval("2.5e-1"), "0.25");
+        assert_eq!(eval("2e"), format_number(2.0 * consts::E));
+        assert_eq!(eval("1e20"), "1e20");
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(eval("sqrt(16)"), "4");
+        assert_eq!(eval("sqrt(2)^2"), "2");
+        assert_eq!(eval("max(1, 5, 3)"), "5");
+        assert_eq!(eval("log(8, 2)"), "3");
+        assert_eq!(eval("round(2.5) + floor(-1.5)"), "1");
+        assert_eq!(eval("sin(0)"), "0");
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(eval("1 / 0"), "infinity");
+        assert_eq!(eval("-1 / 0"), "-infinity");
+        assert_eq!(eval("0 / 0"), "NaN");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            evaluate("1 +"),
+            Err(String::from("unexpected end of expression."))
+        );
+        assert_eq!(evaluate("(1 + 2"), Err(String::from("expected ')'.")));
+        assert_eq!(evaluate("1 2)"), Err(String::from("unexpected ')'.")));
+        assert_eq!(
+            evaluate("1 $ 2"),
+            Err(String::from("unexpected character '$'."))
+        );
+        assert_eq!(
+            evaluate("foo(1)"),
+            Err(String::from("unknown function 'foo'."))
+        );
+        assert_eq!(
+            evaluate("foo"),
+            Err(String::from("unknown constant 'foo'."))
+        );
+        assert_eq!(
+            evaluate("sqrt(1, 2)"),
+            Err(String::from("sqrt takes 1 argument."))
+        );
+        assert_eq!(
+            evaluate("min()"),
+            Err(String::from("min takes at least 1 argument."))
+        );
+        assert_eq!(
+            evaluate("0xzz"),
+            Err(String::from("invalid base 16 number 'zz'."))
+        );
+        assert!(evaluate("2.5!").is_err());
+    }
+}
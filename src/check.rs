@@ -0,0 +1,372 @@
+use std::{
+    fs, mem,
+    path::{Path, PathBuf},
+};
+
+use toml::{
+    Spanned,
+    de::{DeTable, DeValue},
+};
+
+use crate::{
+    config::{Action, ActionKind, CommandLine, Config, Submenu, describe_error},
+    which::program_exists,
+};
+
+/// loads the config at `path` and every submenu it references, printing each problem found, and
+/// a note for each command or submenu overridden by a later file. returns the number of problems.
+pub fn check(path: &Path) -> usize {
+    let config = match Config::load(path) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{e}");
+            return 1;
+        }
+    };
+
+    let mut checker = Checker {
+        files: config
+            .sources
+            .iter()
+            .map(|p| (p.clone(), fs::read_to_string(p).unwrap_or_default()))
+            .collect(),
+        config: &config,
+        problems: Vec::new(),
+        path: Vec::new(),
+        menu_stack: Vec::new(),
+        checked_menus: Vec::new(),
+    };
+    checker.check_config();
+
+    for o in &config.overrides {
+        let file = config.sources.iter().position(|p| *p == o.path);
+        let field = if o.list == "command" {
+            "prefix"
+        } else {
+            "name"
+        };
+        let location = checker.locate(file, &[Key::Field(o.list), Key::Entry(field, o.id.clone())]);
+        println!(
+            "{location}: note: {} '{}' overrides the one in {}.",
+            o.list,
+            o.id,
+            o.replaced.to_string_lossy()
+        );
+    }
+    for problem in &checker.problems {
+        println!("{problem}");
+    }
+    checker.problems.len()
+}
+
+struct Checker<'a> {
+    config: &'a Config,
+    /// the path and contents of every file that has been checked.
+    files: Vec<(PathBuf, String)>,
+    problems: Vec<String>,
+    /// where the value being checked is, from the root of its file.
+    path: Vec<Key>,
+    menu_stack: Vec<String>,
+    checked_menus: Vec<String>,
+}
+
+/// a step from a value to one inside it, used to find where a problem is in a file.
+#[derive(Clone, Debug)]
+enum Key {
+    Field(&'static str),
+    Index(usize),
+    /// the table in an array whose `field` is the given string.
+    Entry(&'static str, String),
+}
+
+impl Checker<'_> {
+    fn check_config(&mut self) {
+        if let Some(default_command) = &self.config.general.default_command
+            && !self.config.command.contains_key(default_command)
+        {
+            self.report(
+                None,
+                &[Key::Field("general"), Key::Field("default_command")],
+                format!("default command '{default_command}' doesn't exist."),
+            );
+        }
+
+        let config = self.config;
+        if let Some(clipboard) = &config.general.clipboard {
+            self.at(&[Key::Field("general")], |c| {
+                c.check_program(clipboard, None, "clipboard")
+            });
+        }
+
+        let mut commands: Vec<_> = config.command.values().collect();
+        commands.sort_by_key(|(_, i)| *i);
+        for (cmd, _) in commands {
+            let path = [
+                Key::Field("command"),
+                Key::Entry("prefix", cmd.prefix.clone()),
+                Key::Field("action"),
+            ];
+            self.at(&path, |c| c.check_action(&cmd.action.kind, None));
+        }
+        let mut submenus: Vec<_> = config.submenus.keys().collect();
+        submenus.sort();
+        for name in submenus {
+            let path = [Key::Field("submenu"), Key::Entry("name", name.clone())];
+            self.at(&path, |c| c.check_menu_by_name(name, None));
+        }
+    }
+
+    /// runs `f` with `keys` added to the path of the value being checked.
+    fn at(&mut self, keys: &[Key], f: impl FnOnce(&mut Self)) {
+        let len = self.path.len();
+        self.path.extend_from_slice(keys);
+        f(self);
+        self.path.truncate(len);
+    }
+
+    /// `file` is the index of the submenu file being checked, or `None` for the main config.
+    fn check_action(&mut self, action: &ActionKind, file: Option<usize>) {
+        match action {
+            ActionKind::List { items, .. } => {
+                for (i, item) in items.iter().enumerate() {
+                    let path = [Key::Field("items"), Key::Index(i), Key::Field("action")];
+                    self.at(&path, |c| c.check_action(&item.action.kind, file));
+                }
+            }
+            ActionKind::Prompt {
+                command,
+                validate_command,
+                ..
+            } => {
+                self.check_command_line(command, file);
+                if let Some(validate_command) = validate_command {
+                    self.check_program(validate_command, file, "validate_command");
+                }
+            }
+            ActionKind::Exec { command, .. } => self.check_program(command, file, "command"),
+            ActionKind::Form { command, .. } => self.check_command_line(command, file),
+            ActionKind::SetVariable {
+                command: Some(command),
+                ..
+            }
+            | ActionKind::Calculator {
+                command: Some(command),
+                ..
+            }
+            | ActionKind::Convert {
+                command: Some(command),
+                ..
+            }
+            | ActionKind::Unicode {
+                command: Some(command),
+                ..
+            }
+            | ActionKind::Files {
+                command: Some(command),
+                ..
+            }
+            | ActionKind::FileSearch {
+                command: Some(command),
+                ..
+            }
+            | ActionKind::SshHosts { command, .. } => self.check_program(command, file, "command"),
+            ActionKind::WebSearch {
+                suggest: Some(command),
+                ..
+            } => self.check_program(command, file, "suggest"),
+            ActionKind::Shell { shell, .. } => self.check_shell(shell.as_ref(), file),
+            ActionKind::Sequence { steps, .. } => {
+                if steps.iter().rev().skip(1).any(Action::waits_for_input) {
+                    self.report(
+                        file,
+                        &[Key::Field("steps")],
+                        String::from("only the last step of a sequence can wait for input."),
+                    );
+                }
+                for (i, step) in steps.iter().enumerate() {
+                    let path = [Key::Field("steps"), Key::Index(i)];
+                    self.at(&path, |c| c.check_action(&step.kind, file));
+                }
+            }
+            ActionKind::Submenu {
+                menu: Some(menu), ..
+            } => self.at(&[Key::Field("menu")], |c| {
+                c.check_menu("<inline>", menu, file)
+            }),
+            ActionKind::Submenu {
+                name: Some(name), ..
+            } => self.at(&[Key::Field("name")], |c| c.check_menu_by_name(name, file)),
+            ActionKind::Submenu { .. } => {
+                self.report(file, &[], String::from("submenus need a name or a menu."))
+            }
+            ActionKind::ListApplications
+            | ActionKind::SetVariable { .. }
+            | ActionKind::Calculator { .. }
+            | ActionKind::Convert { .. }
+            | ActionKind::Copy { .. }
+            | ActionKind::Unicode { .. }
+            | ActionKind::Files { .. }
+            | ActionKind::FileSearch { .. }
+            | ActionKind::RecentFiles
+            | ActionKind::Bookmarks { .. }
+            | ActionKind::WebSearch { .. }
+            | ActionKind::Open { .. }
+            | ActionKind::Exit
+            | ActionKind::LaunchApp(_)
+            | ActionKind::OpenUrl(_) => (),
+        }
+    }
+
+    fn check_menu_by_name(&mut self, name: &str, file: Option<usize>) {
+        if let Some(position) = self.menu_stack.iter().position(|n| n == name) {
+            let mut cycle = self.menu_stack[position..].join(" -> ");
+            cycle.push_str(" -> ");
+            cycle.push_str(name);
+            self.report(file, &[], format!("submenu cycle: {cycle}."));
+            return;
+        }
+        if self.checked_menus.iter().any(|n| n == name) {
+            return;
+        }
+        self.checked_menus.push(name.to_string());
+
+        if let Some(submenu) = self.config.submenus.get(name) {
+            let path = vec![Key::Field("submenu"), Key::Entry("name", name.to_string())];
+            let outer = mem::replace(&mut self.path, path);
+            self.menu_stack.push(name.to_string());
+            self.check_menu(name, submenu, None);
+            self.menu_stack.pop();
+            self.path = outer;
+            return;
+        }
+
+        let (path, source) = match self.config.find_menu(name) {
+            Ok(found) => found,
+            Err(e) => {
+                self.report(file, &[], e);
+                return;
+            }
+        };
+        let submenu = match toml::from_str::<Submenu>(&source) {
+            Ok(submenu) => submenu,
+            Err(e) => {
+                self.problems.push(describe_error(&path, &source, &e));
+                return;
+            }
+        };
+        self.files.push((path, source));
+        let index = self.files.len() - 1;
+
+        let outer = mem::take(&mut self.path);
+        self.menu_stack.push(name.to_string());
+        self.check_menu(name, &submenu, Some(index));
+        self.menu_stack.pop();
+        self.path = outer;
+    }
+
+    fn check_menu(&mut self, name: &str, submenu: &Submenu, file: Option<usize>) {
+        match &submenu.action {
+            action if action.takes_input() => self.check_action(action, file),
+            _ => self.report(
+                file,
+                &[Key::Field("type")],
+                format!("submenus must be a list or a prompt. (encountered in submenu '{name}')"),
+            ),
+        }
+    }
+
+    fn check_command_line(&mut self, command: &CommandLine, file: Option<usize>) {
+        match command {
+            CommandLine::Argv(argv) => self.check_program(argv, file, "command"),
+            CommandLine::Shell(_) => self.check_shell(None, file),
+        }
+    }
+
+    fn check_shell(&mut self, shell: Option<&Vec<String>>, file: Option<usize>) {
+        match shell.or(self.config.general.shell.as_ref()) {
+            Some(shell) => self.check_program(shell, file, "shell"),
+            None => self.check_program(&[String::from("sh")], file, "shell"),
+        }
+    }
+
+    /// `field` is the name of the key holding `command`.
+    fn check_program(&mut self, command: &[String], file: Option<usize>, field: &'static str) {
+        let Some(program) = command.first() else {
+            self.report(file, &[Key::Field(field)], String::from("empty command."));
+            return;
+        };
+        if program.contains("{{") {
+            return; // can't know what this will be until it runs.
+        }
+
+        if !program_exists(program) {
+            self.report(
+                file,
+                &[Key::Field(field), Key::Index(0)],
+                format!("program '{program}' was not found on PATH."),
+            );
+        }
+    }
+
+    /// records a problem with the value at `keys` below the one being checked.
+    fn report(&mut self, file: Option<usize>, keys: &[Key], message: String) {
+        let location = self.locate(file, keys);
+        self.problems.push(format!("{location}: {message}"));
+    }
+
+    /// the file and line of the value at `keys` below the one being checked.
+    fn locate(&self, file: Option<usize>, keys: &[Key]) -> String {
+        // later files override earlier ones, so they're searched first.
+        let candidates: Vec<usize> = match file {
+            Some(i) => vec![i],
+            None => (0..self.config.sources.len().min(self.files.len()))
+                .rev()
+                .collect(),
+        };
+        let keys: Vec<Key> = self.path.iter().chain(keys).cloned().collect();
+        candidates
+            .iter()
+            .find_map(|&i| {
+                let (path, source) = &self.files[i];
+                locate(source, &keys).map(|line| format!("{}:{line}", path.to_string_lossy()))
+            })
+            .or_else(|| {
+                candidates
+                    .first()
+                    .map(|&i| self.files[i].0.to_string_lossy().to_string())
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// the line of the value at `keys` in `source`. if part of it is missing, the line of the closest
+/// value containing it is used instead, unless that's the whole file.
+fn locate(source: &str, keys: &[Key]) -> Option<usize> {
+    let root = DeValue::Table(DeTable::parse(source).ok()?.into_inner());
+    let mut value = &root;
+    let mut span = None;
+    for key in keys {
+        let child = match (key, value) {
+            (Key::Field(name), DeValue::Table(table)) => table.get(*name),
+            (Key::Index(i), DeValue::Array(array)) => array.get(*i),
+            (Key::Entry(field, id), DeValue::Array(array)) => {
+                // an entry that isn't here is in another file.
+                let entry = array.iter().find(|entry| match entry.get_ref() {
+                    DeValue::Table(table) => table
+                        .get(*field)
+                        .map(Spanned::get_ref)
+                        .is_some_and(|v| matches!(v, DeValue::String(s) if s == id)),
+                    _ => false,
+                })?;
+                Some(entry)
+            }
+            _ => None,
+        };
+        let Some(child) = child else {
+            break;
+        };
+        span = Some(child.span());
+        value = child.get_ref();
+    }
+    span.map(|span| source[..span.start].matches('\n').count() + 1)
+}
@@ -0,0 +1,60 @@
+use std::{
+    env,
+    io::Write,
+    os::unix::process::CommandExt,
+    process::{Command, Stdio},
+};
+
+use crate::which::program_exists;
+
+/// commands that read text from stdin and put it on the clipboard, in order of preference.
+const BACKENDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+/// puts `text` on the clipboard, using `command` if it's set, or the first available backend.
+pub fn copy(text: &str, command: Option<&[String]>) -> Result<(), String> {
+    let argv: Vec<&str> = match command {
+        Some(command) => command.iter().map(String::as_str).collect(),
+        None => detect().ok_or_else(|| {
+            String::from(
+                "no clipboard program found. install wl-copy, xclip or xsel, or set general.clipboard.",
+            )
+        })?,
+    };
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| String::from("empty clipboard command."))?;
+
+    // clipboard programs usually stay around to serve the selection, so they need to outlive us.
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("failed to run {program}: {e}"))?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(text.as_bytes())
+        .map_err(|e| format!("failed to write to {program}: {e}"))?;
+
+    match child.wait() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{program} failed with {status}.")),
+        Err(e) => Err(format!("failed to wait for {program}: {e}")),
+    }
+}
+
+fn detect() -> Option<Vec<&'static str>> {
+    let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+    BACKENDS
+        .iter()
+        .filter(|argv| wayland || argv[0] != "wl-copy")
+        .find(|argv| program_exists(argv[0]))
+        .map(|argv| argv.to_vec())
+}
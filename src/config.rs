@@ -1,14 +1,117 @@
+use lazy_regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, de};
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
 pub struct Config {
     #[serde(default)]
     pub general: GeneralConfig,
     #[serde(default)]
     pub variables: HashMap<String, String>,
     #[serde(deserialize_with = "deserialize_commands")]
+    #[schemars(with = "Vec<UserCommand>")]
     pub command: HashMap<String, (UserCommand, usize)>,
+    #[serde(default, rename = "submenu", deserialize_with = "deserialize_submenus")]
+    #[schemars(with = "Vec<NamedSubmenu>")]
+    pub submenus: HashMap<String, Submenu>,
+    /// the directory containing the main config file.
+    #[serde(skip)]
+    pub dir: PathBuf,
+    /// every file this config was merged from.
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+    /// the commands and submenus that replaced ones from an earlier file.
+    #[serde(skip)]
+    pub overrides: Vec<Override>,
+}
+
+/// a command or submenu that replaced one with the same prefix or name from an earlier file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Override {
+    /// `command` or `submenu`.
+    pub list: &'static str,
+    pub id: String,
+    pub path: PathBuf,
+    pub replaced: PathBuf,
+}
+
+impl Config {
+    /// loads a config file, layered on top of the system-wide configs in `XDG_CONFIG_DIRS`.
+    ///
+    /// files listed in `include` are merged before the file that includes them, and later files
+    /// override earlier ones: `general` and `variables` are merged key by key, while `command`
+    /// and `submenu` entries are combined, with a later entry replacing an earlier one with the
+    /// same prefix or name. duplicates within one file are an error. `path` may be missing if
+    /// there's a system-wide config.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let mut layers = Layers::default();
+
+        let system_dirs = env::var("XDG_CONFIG_DIRS").unwrap_or_else(|_| String::from("/etc/xdg"));
+        // XDG_CONFIG_DIRS is in order of importance, so the least important goes first.
+        for dir in system_dirs.split(':').rev().filter(|d| !d.is_empty()) {
+            let system_path = Path::new(dir).join("superspace/config.toml");
+            if system_path.is_file() && system_path != path {
+                layers.merge_file(&system_path)?;
+            }
+        }
+        if path.is_file() {
+            layers.merge_file(path)?;
+        } else if layers.sources.is_empty() {
+            return Err(String::from("failed to find config file."));
+        }
+
+        let mut config: Config = Value::Table(layers.merged)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.message().to_string())?;
+        config.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        config.sources = layers.sources;
+        config.overrides = layers.overrides;
+        Ok(config)
+    }
+
+    /// every path a submenu file may be found at, in order of priority.
+    pub fn menu_search_paths(&self, name: &str) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = self
+            .general
+            .menu_path
+            .iter()
+            .map(|p| self.dir.join(expand_home(p)))
+            .collect();
+        dirs.push(self.dir.clone());
+        if let Some(config_dir) = dirs::config_dir() {
+            dirs.push(config_dir.join("superspace"));
+        }
+
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for dir in dirs {
+            let path = dir.join(format!("{name}.toml"));
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+
+    /// finds a submenu file, returning its path and contents.
+    pub fn find_menu(&self, name: &str) -> Result<(PathBuf, String), String> {
+        let paths = self.menu_search_paths(name);
+        for path in &paths {
+            if let Ok(file) = fs::read_to_string(path) {
+                return Ok((path.clone(), file));
+            }
+        }
+        let searched: Vec<_> = paths.iter().map(|p| p.to_string_lossy()).collect();
+        Err(format!(
+            "submenu '{name}' not found. searched: {}",
+            searched.join(", ")
+        ))
+    }
 }
 
 pub(super) fn deserialize_commands<'de, D: Deserializer<'de, Error = E>, E: de::Error>(
@@ -27,58 +130,507 @@ pub(super) fn deserialize_commands<'de, D: Deserializer<'de, Error = E>, E: de::
     Ok(map)
 }
 
-#[derive(Default, Deserialize, Debug)]
+/// formats a toml error as `path:line:column: message`.
+pub fn describe_error(path: &Path, source: &str, e: &toml::de::Error) -> String {
+    match e.span() {
+        Some(span) => {
+            let before = &source[..span.start.min(source.len())];
+            let line = before.matches('\n').count() + 1;
+            let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+            format!(
+                "{}:{line}:{column}: {}",
+                path.to_string_lossy(),
+                e.message()
+            )
+        }
+        None => format!("{}: {}", path.to_string_lossy(), e.message()),
+    }
+}
+
+/// config files merged so far.
+#[derive(Default)]
+struct Layers {
+    merged: Table,
+    /// every file merged, in the order they were merged in.
+    sources: Vec<PathBuf>,
+    /// the files being merged, each one included by the one before it.
+    including: Vec<PathBuf>,
+    /// the file each command and submenu came from, by list and prefix or name.
+    origins: HashMap<(&'static str, String), PathBuf>,
+    overrides: Vec<Override>,
+}
+
+impl Layers {
+    fn merge_file(&mut self, path: &Path) -> Result<(), String> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.sources.contains(&canonical) || self.including.contains(&canonical) {
+            return Ok(()); // already merged, or an include cycle.
+        }
+
+        let file = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.to_string_lossy()))?;
+        let mut table: Table =
+            toml::from_str(&file).map_err(|e| describe_error(path, &file, &e))?;
+
+        if let Some(include) = table.remove("include") {
+            let include: Vec<String> = include.try_into().map_err(|_| {
+                format!(
+                    "{}: include must be a list of paths",
+                    path.to_string_lossy()
+                )
+            })?;
+            let dir = path.parent().unwrap_or(Path::new(""));
+            self.including.push(canonical.clone());
+            for included in include {
+                self.merge_file(&dir.join(expand_home(&included)))?;
+            }
+            self.including.pop();
+        }
+        self.sources.push(canonical.clone());
+
+        for (key, value) in table {
+            let list = match key.as_str() {
+                "command" => "command",
+                "submenu" => "submenu",
+                _ => {
+                    match (self.merged.get_mut(&key), value) {
+                        (Some(Value::Table(old)), Value::Table(new))
+                            if key == "general" || key == "variables" =>
+                        {
+                            old.extend(new)
+                        }
+                        (_, value) => {
+                            self.merged.insert(key, value);
+                        }
+                    }
+                    continue;
+                }
+            };
+            let Value::Array(new) = value else {
+                self.merged.insert(key, value);
+                continue;
+            };
+            let field = if list == "command" { "prefix" } else { "name" };
+            let mut ids = Vec::new();
+            for entry in &new {
+                let Some(id) = entry.get(field).and_then(Value::as_str) else {
+                    continue;
+                };
+                if ids.contains(&id) {
+                    return Err(format!(
+                        "duplicate {list} {id} (in {})",
+                        path.to_string_lossy()
+                    ));
+                }
+                ids.push(id);
+            }
+
+            let Value::Array(old) = self
+                .merged
+                .entry(key)
+                .or_insert_with(|| Value::Array(Vec::new()))
+            else {
+                continue;
+            };
+            for entry in new {
+                let id = entry.get(field).and_then(Value::as_str).map(str::to_string);
+                // an entry from a later file replaces the earlier one, keeping its position.
+                match old
+                    .iter_mut()
+                    .find(|o| id.is_some() && o.get(field).and_then(Value::as_str) == id.as_deref())
+                {
+                    Some(o) => *o = entry,
+                    None => old.push(entry),
+                }
+                let Some(id) = id else {
+                    continue;
+                };
+                if let Some(replaced) = self.origins.insert((list, id.clone()), canonical.clone()) {
+                    self.overrides.push(Override {
+                        list,
+                        id,
+                        path: canonical.clone(),
+                        replaced,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deser: D) -> Result<Option<Regex>, D::Error> {
+    Option::<String>::deserialize(deser)?
+        .map(|s| Regex::new(&s).map_err(de::Error::custom))
+        .transpose()
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct NamedSubmenu {
+    name: String,
+    #[serde(flatten)]
+    menu: Submenu,
+}
+
+fn deserialize_submenus<'de, D: Deserializer<'de, Error = E>, E: de::Error>(
+    deser: D,
+) -> Result<HashMap<String, Submenu>, D::Error> {
+    let vec: Vec<NamedSubmenu> = Vec::deserialize(deser)?;
+    let mut map = HashMap::new();
+
+    for NamedSubmenu { name, menu } in vec {
+        if map.contains_key(&name) {
+            return Err(E::custom(format!("duplicate submenu {name}")));
+        }
+        map.insert(name, menu);
+    }
+
+    Ok(map)
+}
+
+#[derive(Default, Deserialize, JsonSchema, Debug)]
 pub struct GeneralConfig {
     pub default_command: Option<String>,
     pub prompt: Option<String>,
+    /// extra directories to search for submenu files. relative paths are resolved from the
+    /// directory of the main config file.
+    #[serde(default)]
+    pub menu_path: Vec<String>,
+    /// the default shell for shell actions. defaults to `["sh", "-c"]`.
+    pub shell: Option<Vec<String>>,
+    /// report undefined variables as errors instead of replacing them with "".
+    #[serde(default)]
+    pub strict_variables: bool,
     #[serde(default = "search_apps_default")]
     pub search_apps: bool,
+    /// the command used to copy to the clipboard, which reads the text from stdin. by default,
+    /// wl-copy, xclip or xsel is used, whichever is installed.
+    pub clipboard: Option<Vec<String>>,
 }
 
 fn search_apps_default() -> bool {
     true
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
 pub struct Submenu {
     pub prompt: Option<String>,
     #[serde(flatten)]
-    pub action: Action,
+    pub action: ActionKind,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
 pub struct UserCommand {
     pub prefix: String,
     pub description: String,
     pub action: Action,
 }
 
-#[derive(Deserialize, Debug)]
+fn files_root_default() -> String {
+    String::from("~")
+}
+
+fn file_search_roots_default() -> Vec<String> {
+    vec![String::from("~")]
+}
+
+fn characters_default() -> bool {
+    true
+}
+
+fn stop_on_failure_default() -> bool {
+    true
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct Action {
+    #[serde(flatten)]
+    pub kind: ActionKind,
+    /// ask for confirmation with this message before running the action. only `y` runs it, and
+    /// enter or `n` cancels.
+    pub confirm: Option<String>,
+}
+
+impl Action {
+    /// whether running this action leaves superspace waiting for more input.
+    pub fn waits_for_input(&self) -> bool {
+        self.confirm.is_some()
+            || self.kind.takes_input()
+            || match &self.kind {
+                ActionKind::Submenu { .. } => true,
+                ActionKind::Sequence { steps, .. } => {
+                    steps.last().is_some_and(Action::waits_for_input)
+                }
+                _ => false,
+            }
+    }
+}
+
+impl From<ActionKind> for Action {
+    fn from(kind: ActionKind) -> Self {
+        Self {
+            kind,
+            confirm: None,
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
 #[serde(tag = "type", rename_all = "snake_case")]
-pub enum Action {
+pub enum ActionKind {
     ListApplications,
     List {
         items: Vec<ListItem>,
+        /// `copy` puts the name of the selected item on the clipboard instead of running its
+        /// action.
+        #[serde(default)]
+        on_enter: OnEnter,
     },
+    /// runs `command` with `{{INPUT}}` on enter. if `command` is a string, it's run through the
+    /// shell with the input quoted.
     Prompt {
-        command: Vec<String>,
+        command: CommandLine,
         #[serde(default)]
         output: OutputMode,
+        #[serde(default, deserialize_with = "deserialize_regex")]
+        #[schemars(with = "Option<String>")]
+        validate: Option<Regex>,
+        /// run with `{{INPUT}}` on each keystroke. a non-zero exit status marks the input as
+        /// invalid, and its output is used as the validation message. it's killed after
+        /// `timeout_ms`, or half a second if that isn't set.
+        validate_command: Option<Vec<String>>,
+        /// `copy` puts the output of the command on the clipboard.
+        #[serde(default)]
+        on_enter: OnEnter,
+        #[serde(flatten)]
+        options: ExecOptions,
     },
     Exec {
         command: Vec<String>,
+        #[serde(flatten)]
+        options: ExecOptions,
+    },
+    /// a command line run through a shell. substituted variables are quoted.
+    Shell {
+        command: String,
+        shell: Option<Vec<String>>,
+        #[serde(flatten)]
+        options: ExecOptions,
     },
     Exit,
-    Submenu {
+    /// asks for each field in turn, then runs `command` with a variable for each. if `command` is
+    /// a string, it's run through the shell with the values quoted.
+    Form {
+        fields: Vec<FormField>,
+        command: CommandLine,
+        #[serde(flatten)]
+        options: ExecOptions,
+    },
+    /// runs each step in order. only the last step may wait for input.
+    Sequence {
+        steps: Vec<Action>,
+        #[serde(default = "stop_on_failure_default")]
+        stop_on_failure: bool,
+        /// store the trimmed output of each command step in this variable, replacing the output
+        /// of the step before. the last step isn't captured, and neither are detached ones.
+        capture: Option<String>,
+    },
+    /// stores `value`, or the trimmed output of `command`, in a variable.
+    SetVariable {
         name: String,
+        value: Option<String>,
+        command: Option<Vec<String>>,
+        #[serde(flatten)]
+        options: ExecOptions,
+    },
+    /// opens a submenu, either by name or defined inline with `menu`.
+    Submenu {
+        name: Option<String>,
+        menu: Option<Box<Submenu>>,
         #[serde(default)]
         variables: HashMap<String, String>,
     },
+    /// evaluates the input as a math expression on each keystroke. on enter, `command` is run
+    /// with `{{RESULT}}`, or the result is output if there's no command.
+    Calculator {
+        command: Option<Vec<String>>,
+        #[serde(default)]
+        on_enter: OnEnter,
+        #[serde(flatten)]
+        options: ExecOptions,
+    },
+    /// converts input like `12 mib to mb` on each keystroke. on enter, `command` is run with
+    /// `{{RESULT}}`, or the result is output if there's no command.
+    Convert {
+        #[serde(default)]
+        units: Vec<UnitDefinition>,
+        command: Option<Vec<String>>,
+        #[serde(default)]
+        on_enter: OnEnter,
+        #[serde(flatten)]
+        options: ExecOptions,
+    },
+    /// picks an emoji or other character, which is copied, or passed to `command` as
+    /// `{{CHAR}}`. for example, `["wtype", "{{CHAR}}"]` types it instead. emoji are searched by
+    /// name and by their gemoji shortcodes, like `:smile:`, and other characters by name.
+    Unicode {
+        /// include characters that aren't emoji, like `→` or `é`.
+        #[serde(default = "characters_default")]
+        characters: bool,
+        command: Option<Vec<String>>,
+    },
+    /// browses the filesystem from `root`. typing `/` opens the selected directory, and enter
+    /// opens the selected file with its default application, or runs `command` with `{{PATH}}`.
+    /// input starting with `/` or `~/` browses from the root or home directory instead.
+    Files {
+        #[serde(default = "files_root_default")]
+        root: String,
+        /// show hidden files. they're also shown when the input starts with `.`.
+        #[serde(default)]
+        hidden: bool,
+        command: Option<Vec<String>>,
+        #[serde(flatten)]
+        options: ExecOptions,
+    },
+    /// searches for files under `roots` as you type, skipping files ignored by `.gitignore`.
+    /// enter opens the best match like `files` does.
+    FileSearch {
+        #[serde(default = "file_search_roots_default")]
+        roots: Vec<String>,
+        /// globs of paths to skip, like `**/node_modules`.
+        #[serde(default)]
+        exclude: Vec<String>,
+        #[serde(default)]
+        hidden: bool,
+        /// a file to save the paths that were found in. when it exists, it's searched right away
+        /// instead of waiting for the walk, and updated once the walk is done.
+        index: Option<String>,
+        command: Option<Vec<String>>,
+        #[serde(flatten)]
+        options: ExecOptions,
+    },
+    /// lists recently used files, as saved by other apps in `recently-used.xbel`. enter opens
+    /// the selected file with its default application.
+    RecentFiles,
+    /// lists the hosts in `~/.ssh/config` and `~/.ssh/known_hosts`. enter runs `command` with
+    /// `{{HOST}}`, usually a terminal, like `["foot", "ssh", "{{HOST}}"]`.
+    SshHosts {
+        command: Vec<String>,
+        #[serde(flatten)]
+        options: ExecOptions,
+    },
+    /// lists the bookmarks from firefox and chromium-based browsers. enter opens the selected
+    /// one in the default browser.
+    Bookmarks {
+        /// also list this many of the most visited pages from each browser's history.
+        #[serde(default)]
+        history: usize,
+    },
+    /// searches the web for the input, like `g rust nucleo`. enter opens `url` in the default
+    /// browser, with `{{INPUT}}` replaced by the input, or by the suggestion selected with `up`
+    /// and `down`. variables in `url` are url-encoded unless the `raw` filter is used.
+    WebSearch {
+        url: String,
+        /// a command that prints suggestions for `{{INPUT}}` as it's typed, one per line.
+        suggest: Option<Vec<String>>,
+    },
+    /// opens a file or url with its default application.
+    Open {
+        target: String,
+    },
+    /// puts `text` on the clipboard.
+    Copy {
+        text: String,
+    },
     #[serde(skip)]
     LaunchApp(PathBuf),
+    /// opens a url found by a generated list. unlike `open`, it isn't a template.
+    #[serde(skip)]
+    OpenUrl(String),
 }
 
-#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+impl ActionKind {
+    /// whether this action waits for more input after it's selected.
+    pub fn takes_input(&self) -> bool {
+        matches!(
+            self,
+            ActionKind::List { .. }
+                | ActionKind::Prompt { .. }
+                | ActionKind::Form { .. }
+                | ActionKind::Calculator { .. }
+                | ActionKind::Convert { .. }
+                | ActionKind::Unicode { .. }
+                | ActionKind::Files { .. }
+                | ActionKind::FileSearch { .. }
+                | ActionKind::RecentFiles
+                | ActionKind::SshHosts { .. }
+                | ActionKind::Bookmarks { .. }
+                | ActionKind::WebSearch { .. }
+                | ActionKind::ListApplications
+        )
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct FormField {
+    pub name: String,
+    pub prompt: Option<String>,
+    pub default: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    #[schemars(with = "Option<String>")]
+    pub validate: Option<Regex>,
+    pub choices: Option<Vec<String>>,
+}
+
+/// a command, either as a list of arguments, or as a line run through the shell, where
+/// substituted variables are quoted.
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(untagged)]
+pub enum CommandLine {
+    Argv(Vec<String>),
+    Shell(String),
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Default, Clone)]
+pub struct ExecOptions {
+    /// extra environment variables. values are templated like the command.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub cwd: Option<String>,
+    /// if set, wait for the command and kill it after this many milliseconds.
+    pub timeout_ms: Option<u64>,
+    /// run the command in its own process group, so it outlives superspace.
+    #[serde(default)]
+    pub detach: bool,
+}
+
+/// what happens when enter is pressed.
+#[derive(Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnEnter {
+    /// run the command, or the selected item's action. results are output if there's no command.
+    #[default]
+    Run,
+    /// copy the result to the clipboard.
+    Copy,
+}
+
+/// a custom unit for `convert`, like `{ name = "furlong", value = "201.168 m" }`.
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct UnitDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// an amount of a built-in unit, or of a unit defined before this one.
+    pub value: String,
+    /// added to an amount before it's scaled by `value`, for scales that don't start at zero,
+    /// like `{ name = "Rø", value = "40/21 °C", offset = -7.5 }` for rømer.
+    #[serde(default)]
+    pub offset: f64,
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum OutputMode {
     #[default]
@@ -87,14 +639,114 @@ pub enum OutputMode {
     Continuous,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
 pub struct ListItem {
     pub name: String,
+    /// shown alongside the name in list frames.
+    pub description: Option<String>,
     pub action: Action,
+    /// the text matched against the input, if it isn't just the name.
+    #[serde(skip)]
+    pub search: Option<String>,
+    /// variables set while the action runs, like `CHAR` for unicode items.
+    #[serde(skip)]
+    pub variables: Vec<(&'static str, String)>,
 }
 
 impl AsRef<str> for ListItem {
     fn as_ref(&self) -> &str {
-        &self.name
+        self.search.as_deref().unwrap_or(&self.name)
+    }
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            let home = dirs::home_dir().expect("no home directory");
+            match rest.trim_start_matches('/') {
+                "" => home,
+                rest => home.join(rest),
+            }
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path.canonicalize().unwrap()
+    }
+
+    fn prefixes(layers: &Layers) -> Vec<&str> {
+        layers.merged["command"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["prefix"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn merge_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let system = write(
+            dir.path(),
+            "system.toml",
+            "[general]\na = 1\nb = 1\n[[command]]\nprefix = \"x\"\nname = \"system\"\n[[command]]\nprefix = \"y\"\n",
+        );
+        let included = write(
+            dir.path(),
+            "included.toml",
+            "[general]\nb = 2\n[[command]]\nprefix = \"z\"\n",
+        );
+        let user = write(
+            dir.path(),
+            "user.toml",
+            "include = [\"included.toml\"]\n[[command]]\nprefix = \"x\"\nname = \"user\"\n",
+        );
+
+        let mut layers = Layers::default();
+        layers.merge_file(&system).unwrap();
+        layers.merge_file(&user).unwrap();
+
+        assert_eq!(layers.sources, [system.clone(), included, user.clone()]);
+        assert_eq!(layers.merged["general"]["a"].as_integer(), Some(1));
+        assert_eq!(layers.merged["general"]["b"].as_integer(), Some(2));
+        // the replaced command keeps its position.
+        assert_eq!(prefixes(&layers), ["x", "y", "z"]);
+        assert_eq!(layers.merged["command"][0]["name"].as_str(), Some("user"));
+        assert_eq!(
+            layers.overrides,
+            [Override {
+                list: "command",
+                id: String::from("x"),
+                path: user,
+                replaced: system,
+            }]
+        );
+    }
+
+    #[test]
+    fn duplicates_in_one_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = write(dir.path(), "base.toml", "[[submenu]]\nname = \"a\"\n");
+        let layer = write(
+            dir.path(),
+            "layer.toml",
+            "[[submenu]]\nname = \"a\"\n[[submenu]]\nname = \"a\"\n",
+        );
+
+        let mut layers = Layers::default();
+        layers.merge_file(&base).unwrap();
+        let e = layers.merge_file(&layer).unwrap_err();
+        assert_eq!(
+            e,
+            format!("duplicate submenu a (in {})", layer.to_string_lossy())
+        );
     }
 }
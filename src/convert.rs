@@ -0,0 +1,345 @@
+use std::f64::consts::PI;
+
+use crate::{calculator, config::UnitDefinition};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Time,
+    Data,
+    Temperature,
+    Volume,
+    Area,
+    Speed,
+    Angle,
+    Energy,
+    Pressure,
+}
+
+/// a unit is converted to its dimension's base unit with `(value + offset) * factor`.
+#[derive(Debug, Clone, Copy)]
+struct Unit<'a> {
+    symbol: &'a str,
+    dimension: Dimension,
+    factor: f64,
+    offset: f64,
+}
+
+/// (names, dimension, factor, offset). the first name is the one results are shown with.
+#[rustfmt::skip]
+const UNITS: &[(&[&str], Dimension, f64, f64)] = {
+    use Dimension::*;
+    &[
+        (&["m", "meter", "meters", "metre", "metres"], Length, 1.0, 0.0),
+        (&["km", "kilometer", "kilometers", "kilometre", "kilometres"], Length, 1e3, 0.0),
+        (&["cm", "centimeter", "centimeters", "centimetre", "centimetres"], Length, 1e-2, 0.0),
+        (&["mm", "millimeter", "millimeters", "millimetre", "millimetres"], Length, 1e-3, 0.0),
+        (&["µm", "um", "micrometer", "micrometers", "micron", "microns"], Length, 1e-6, 0.0),
+        (&["nm", "nanometer", "nanometers"], Length, 1e-9, 0.0),
+        (&["in", "inch", "inches", "\""], Length, 0.0254, 0.0),
+        (&["ft", "foot", "feet", "'"], Length, 0.3048, 0.0),
+        (&["yd", "yard", "yards"], Length, 0.9144, 0.0),
+        (&["mi", "mile", "miles"], Length, 1609.344, 0.0),
+        (&["nmi"], Length, 1852.0, 0.0),
+
+        (&["kg", "kilogram", "kilograms", "kilo", "kilos"], Mass, 1.0, 0.0),
+        (&["g", "gram", "grams"], Mass, 1e-3, 0.0),
+        (&["mg", "milligram", "milligrams"], Mass, 1e-6, 0.0),
+        (&["t", "tonne", "tonnes"], Mass, 1e3, 0.0),
+        (&["lb", "lbs", "pound", "pounds"], Mass, 0.453_592_37, 0.0),
+        (&["oz", "ounce", "ounces"], Mass, 0.028_349_523_125, 0.0),
+        (&["st", "stone", "stones"], Mass, 6.350_293_18, 0.0),
+
+        (&["s", "sec", "secs", "second", "seconds"], Time, 1.0, 0.0),
+        (&["ns", "nanosecond", "nanoseconds"], Time, 1e-9, 0.0),
+        (&["µs", "us", "microsecond", "microseconds"], Time, 1e-6, 0.0),
+        (&["ms", "millisecond", "milliseconds"], Time, 1e-3, 0.0),
+        (&["min", "mins", "minute", "minutes"], Time, 60.0, 0.0),
+        (&["h", "hr", "hrs", "hour", "hours"], Time, 3600.0, 0.0),
+        (&["d", "day", "days"], Time, 86_400.0, 0.0),
+        (&["wk", "week", "weeks"], Time, 604_800.0, 0.0),
+        (&["month", "months"], Time, 2_629_800.0, 0.0),
+        (&["yr", "year", "years"], Time, 31_557_600.0, 0.0),
+
+        // bytes come before bits, so a lowercase `mb` means megabytes.
+        (&["B", "byte", "bytes"], Data, 1.0, 0.0),
+        (&["kB", "KB", "kilobyte", "kilobytes"], Data, 1e3, 0.0),
+        (&["MB", "megabyte", "megabytes"], Data, 1e6, 0.0),
+        (&["GB", "gigabyte", "gigabytes"], Data, 1e9, 0.0),
+        (&["TB", "terabyte", "terabytes"], Data, 1e12, 0.0),
+        (&["PB", "petabyte", "petabytes"], Data, 1e15, 0.0),
+        (&["KiB", "kibibyte", "kibibytes"], Data, 1024.0, 0.0),
+        (&["MiB", "mebibyte", "mebibytes"], Data, 1_048_576.0, 0.0),
+        (&["GiB", "gibibyte", "gibibytes"], Data, 1_073_741_824.0, 0.0),
+        (&["TiB", "tebibyte", "tebibytes"], Data, 1_099_511_627_776.0, 0.0),
+        (&["PiB", "pebibyte", "pebibytes"], Data, 1_125_899_906_842_624.0, 0.0),
+        (&["b", "bit", "bits"], Data, 0.125, 0.0),
+        (&["kb", "kbit", "kilobit", "kilobits"], Data, 125.0, 0.0),
+        (&["Mb", "Mbit", "megabit", "megabits"], Data, 125e3, 0.0),
+        (&["Gb", "Gbit", "gigabit", "gigabits"], Data, 125e6, 0.0),
+
+        (&["K", "kelvin"], Temperature, 1.0, 0.0),
+        (&["°C", "C", "celsius"], Temperature, 1.0, 273.15),
+        (&["°F", "F", "fahrenheit"], Temperature, 5.0 / 9.0, 459.67),
+
+        (&["L", "l", "liter", "liters", "litre", "litres"], Volume, 1.0, 0.0),
+        (&["mL", "ml", "milliliter", "milliliters", "millilitre", "millilitres"], Volume, 1e-3, 0.0),
+        (&["cL", "cl"], Volume, 1e-2, 0.0),
+        (&["dL", "dl"], Volume, 0.1, 0.0),
+        (&["m³", "m3"], Volume, 1e3, 0.0),
+        (&["cm³", "cm3", "cc"], Volume, 1e-3, 0.0),
+        (&["gal", "gallon", "gallons"], Volume, 3.785_411_784, 0.0),
+        (&["qt", "quart", "quarts"], Volume, 0.946_352_946, 0.0),
+        (&["pt", "pint", "pints"], Volume, 0.473_176_473, 0.0),
+        (&["cup", "cups"], Volume, 0.236_588_236_5, 0.0),
+        (&["floz"], Volume, 0.029_573_529_562_5, 0.0),
+        (&["tbsp"], Volume, 0.014_786_764_781_25, 0.0),
+        (&["tsp"], Volume, 0.004_928_921_593_75, 0.0),
+
+        (&["m²", "m2"], Area, 1.0, 0.0),
+        (&["km²", "km2"], Area, 1e6, 0.0),
+        (&["cm²", "cm2"], Area, 1e-4, 0.0),
+        (&["ha", "hectare", "hectares"], Area, 1e4, 0.0),
+        (&["acre", "acres"], Area, 4_046.856_422_4, 0.0),
+        (&["ft²", "ft2"], Area, 0.092_903_04, 0.0),
+        (&["in²", "in2"], Area, 0.000_645_16, 0.0),
+        (&["mi²", "mi2"], Area, 2_589_988.110_336, 0.0),
+
+        (&["m/s", "mps"], Speed, 1.0, 0.0),
+        (&["km/h", "kph", "kmh"], Speed, 1.0 / 3.6, 0.0),
+        (&["mph"], Speed, 0.447_04, 0.0),
+        (&["ft/s", "fps"], Speed, 0.3048, 0.0),
+        (&["kn", "knot", "knots"], Speed, 1852.0 / 3600.0, 0.0),
+
+        (&["rad", "radian", "radians"], Angle, 1.0, 0.0),
+        (&["°", "deg", "degree", "degrees"], Angle, PI / 180.0, 0.0),
+        (&["grad", "gradian", "gradians"], Angle, PI / 200.0, 0.0),
+        (&["turn", "turns"], Angle, 2.0 * PI, 0.0),
+
+        (&["J", "joule", "joules"], Energy, 1.0, 0.0),
+        (&["kJ", "kilojoule", "kilojoules"], Energy, 1e3, 0.0),
+        (&["cal", "calorie", "calories"], Energy, 4.184, 0.0),
+        (&["kcal", "kilocalorie", "kilocalories"], Energy, 4184.0, 0.0),
+        (&["Wh"], Energy, 3600.0, 0.0),
+        (&["kWh"], Energy, 3.6e6, 0.0),
+        (&["eV"], Energy, 1.602_176_634e-19, 0.0),
+
+        (&["Pa", "pascal", "pascals"], Pressure, 1.0, 0.0),
+        (&["kPa"], Pressure, 1e3, 0.0),
+        (&["bar"], Pressure, 1e5, 0.0),
+        (&["atm"], Pressure, 101_325.0, 0.0),
+        (&["psi"], Pressure, 6_894.757_293_168, 0.0),
+        (&["mmHg"], Pressure, 133.322_387_415, 0.0),
+    ]
+};
+
+/// converts input like `12 mib to mb` or `70 °F in C`. returns the converted value and the
+/// symbol of the unit it was converted to.
+pub fn convert<'a>(input: &str, units: &'a [UnitDefinition]) -> Result<(f64, &'a str), String> {
+    let (from, to) = [" to ", " in ", " as ", "->"]
+        .iter()
+        .find_map(|separator| input.rsplit_once(separator))
+        .ok_or_else(|| String::from("expected '<amount> <unit> to <unit>'."))?;
+
+    let (amount, from) = split_unit(from.trim());
+    let amount = calculator::evaluate(amount)?;
+    let from = find(from, units)?;
+    let to = find(to.trim(), units)?;
+
+    if from.dimension != to.dimension {
+        return Err(format!(
+            "can't convert {} to {}.",
+            dimension_name(from.dimension),
+            dimension_name(to.dimension)
+        ));
+    }
+
+    let scaled = (amount + from.offset) * from.factor / to.factor;
+    let value = scaled - to.offset;
+    // taking away the offset can leave float noise where the result should be 0.
+    if value.abs() < scaled.abs() * 1e-12 {
+        Ok((0.0, to.symbol))
+    } else {
+        Ok((value, to.symbol))
+    }
+}
+
+/// splits `12 mib` or `12mib` into the amount and the unit. an exponent, like in `1e3m`, is part
+/// of the amount.
+fn split_unit(input: &str) -> (&str, &str) {
+    if let Some((amount, unit)) = input.rsplit_once(char::is_whitespace) {
+        return (amount, unit);
+    }
+    let mut start = 0;
+    loop {
+        let i = match input[start..].find(|c: char| c.is_alphabetic() || "°µ'\"".contains(c)) {
+            Some(i) if start + i > 0 => start + i,
+            _ => return (input, ""),
+        };
+        let (amount, unit) = input.split_at(i);
+        let exponent = unit
+            .strip_prefix(['e', 'E'])
+            .map(|e| e.trim_start_matches(['+', '-']))
+            .filter(|e| e.starts_with(|c: char| c.is_ascii_digit()));
+        match exponent {
+            Some(exponent) if amount.ends_with(|c: char| c.is_ascii_digit() || c == '.') => {
+                start = input.len()
+                    - exponent
+                        .trim_start_matches(|c: char| c.is_ascii_digit())
+                        .len();
+            }
+            _ => return (amount, unit),
+        }
+    }
+}
+
+fn find<'a>(name: &str, units: &'a [UnitDefinition]) -> Result<Unit<'a>, String> {
+    if name.is_empty() {
+        return Err(String::from("missing unit."));
+    }
+    find_in(name, units).ok_or_else(|| format!("unknown unit '{name}'."))
+}
+
+/// user-defined units can only refer to the units defined before them, so there are no cycles.
+fn find_in<'a>(name: &str, units: &'a [UnitDefinition]) -> Option<Unit<'a>> {
+    for (i, definition) in units.iter().enumerate().rev() {
+        let names = std::iter::once(&definition.name).chain(&definition.aliases);
+        if names.into_iter().any(|n| n == name) {
+            let (amount, base) = split_unit(definition.value.trim());
+            let amount = calculator::evaluate(amount).ok()?;
+            let base = find_in(base, &units[..i])?;
+            // (x + offset) * amount is in the base unit, which has its own offset.
+            return Some(Unit {
+                symbol: &definition.name,
+                dimension: base.dimension,
+                factor: amount * base.factor,
+                offset: definition.offset + base.offset / amount,
+            });
+        }
+    }
+
+    // exact matches win, so `Mb` and `MB` can be told apart.
+    let builtin = |matches: &dyn Fn(&str) -> bool| {
+        UNITS
+            .iter()
+            .find(|(names, ..)| names.iter().any(|n| matches(n)))
+            .map(|&(names, dimension, factor, offset)| Unit {
+                symbol: names[0],
+                dimension,
+                factor,
+                offset,
+            })
+    };
+    builtin(&|n| n == name).or_else(|| builtin(&|n| n.eq_ignore_ascii_case(name)))
+}
+
+fn dimension_name(dimension: Dimension) -> &'static str {
+    match dimension {
+        Dimension::Length => "length",
+        Dimension::Mass => "mass",
+        Dimension::Time => "time",
+        Dimension::Data => "data",
+        Dimension::Temperature => "temperature",
+        Dimension::Volume => "volume",
+        Dimension::Area => "area",
+        Dimension::Speed => "speed",
+        Dimension::Angle => "angle",
+        Dimension::Energy => "energy",
+        Dimension::Pressure => "pressure",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str, units: &[UnitDefinition]) -> String {
+        let (value, unit) = convert(input, units).unwrap();
+        format!("{} {unit}", calculator::format_number(value))
+    }
+
+    fn unit(name: &str, value: &str, offset: f64) -> UnitDefinition {
+        UnitDefinition {
+            name: name.to_string(),
+            aliases: Vec::new(),
+            value: value.to_string(),
+            offset,
+        }
+    }
+
+    #[test]
+    fn builtin_units() {
+        assert_eq!(run("1 km to m", &[]), "1000 m");
+        assert_eq!(run("12 in in cm", &[]), "30.48 cm");
+        assert_eq!(run("1 MiB as KiB", &[]), "1024 KiB");
+        assert_eq!(run("2 * 3 ft -> in", &[]), "72 in");
+        assert_eq!(
+            run("90 deg to rad", &[]),
+            format!("{} rad", calculator::format_number(PI / 2.0))
+        );
+    }
+
+    #[test]
+    fn case() {
+        // exact matches win, otherwise case is ignored.
+        assert_eq!(run("1 MB to kB", &[]), "1000 kB");
+        assert_eq!(run("1 Mb to kB", &[]), "125 kB");
+        assert_eq!(run("1 mb to kB", &[]), "1000 kB");
+        assert_eq!(run("1 mb to kb", &[]), "8000 kb");
+    }
+
+    #[test]
+    fn temperature() {
+        assert_eq!(run("100 C to F", &[]), "212 °F");
+        assert_eq!(run("32 °F to °C", &[]), "0 °C");
+        assert_eq!(run("0 K to C", &[]), "-273.15 °C");
+        assert_eq!(run("-40 F to C", &[]), "-40 °C");
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(split_unit("12 mib"), ("12", "mib"));
+        assert_eq!(split_unit("12mib"), ("12", "mib"));
+        assert_eq!(split_unit("1e3m"), ("1e3", "m"));
+        assert_eq!(split_unit("2.5e-3km"), ("2.5e-3", "km"));
+        assert_eq!(split_unit("5em"), ("5", "em"));
+        assert_eq!(split_unit("1e3"), ("1e3", ""));
+        assert_eq!(split_unit("70°F"), ("70", "°F"));
+        assert_eq!(split_unit("m"), ("m", ""));
+        assert_eq!(run("1e3m to km", &[]), "1 km");
+    }
+
+    #[test]
+    fn custom_units() {
+        let units = [
+            unit("furlong", "201.168 m", 0.0),
+            unit("chain", "0.1 furlong", 0.0),
+            unit("Rø", "40/21 °C", -7.5),
+            unit("R", "5/9 K", 0.0),
+        ];
+        assert_eq!(run("1 furlong to m", &units), "201.168 m");
+        assert_eq!(run("10 chain to furlong", &units), "1 furlong");
+        assert_eq!(run("7.5 Rø to C", &units), "0 °C");
+        assert_eq!(run("60 Rø to C", &units), "100 °C");
+        assert_eq!(run("100 C to Rø", &units), "60 Rø");
+        assert_eq!(run("491.67 R to F", &units), "32 °F");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            convert("12 m", &[]),
+            Err(String::from("expected '<amount> <unit> to <unit>'."))
+        );
+        assert_eq!(convert("12 to m", &[]), Err(String::from("missing unit.")));
+        assert_eq!(
+            convert("12 parsec to m", &[]),
+            Err(String::from("unknown unit 'parsec'."))
+        );
+        assert_eq!(
+            convert("1 kg to m", &[]),
+            Err(String::from("can't convert mass to length."))
+        );
+    }
+}
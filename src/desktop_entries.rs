@@ -3,7 +3,7 @@ use std::{borrow::Cow, env, ffi::OsStr, fs};
 use gio::{DesktopAppInfo, prelude::AppInfoExt};
 use lazy_regex::regex_captures;
 
-use crate::config::{Action, ListItem};
+use crate::config::{ActionKind, ListItem};
 
 pub fn get_desktop_entries() -> Result<Vec<ListItem>, String> {
     let directories = env::var("XDG_DATA_DIRS").map_err(|e| {
@@ -35,7 +35,10 @@ pub fn get_desktop_entries() -> Result<Vec<ListItem>, String> {
                 };
                 entries.push(ListItem {
                     name: info.name().to_string(),
-                    action: Action::LaunchApp(path),
+                    description: None,
+                    action: ActionKind::LaunchApp(path).into(),
+                    search: None,
+                    variables: Vec::new(),
                 });
             }
         }
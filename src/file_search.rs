@@ -0,0 +1,209 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, LazyLock, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+
+use globset::{Glob, GlobSetBuilder};
+use ignore::{WalkBuilder, WalkState};
+use nucleo::{
+    Injector, Nucleo,
+    pattern::{CaseMatching, Normalization},
+};
+
+/// how many matches are shown when there's no `--max-items`.
+pub const MAX_RESULTS: usize = 100;
+
+/// a recursive file search. paths are found by a background thread and matched by nucleo's
+/// thread pool, so results come in while the user types.
+pub struct FileSearch {
+    nucleo: Nucleo<PathBuf>,
+    query: String,
+    walking: Arc<AtomicBool>,
+    /// whether the walk was still going at the last tick.
+    was_walking: bool,
+    /// stops the walk when the search is dropped.
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Drop for FileSearch {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl std::fmt::Debug for FileSearch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileSearch")
+            .field("query", &self.query)
+            .field("items", &self.nucleo.snapshot().item_count())
+            .field("walking", &self.is_walking())
+            .finish()
+    }
+}
+
+impl FileSearch {
+    /// starts walking `roots`, skipping paths that match a glob in `exclude`. if `index` is set,
+    /// the paths saved there last time are searched instead, and the index is updated once the
+    /// walk is done.
+    pub fn new(
+        roots: Vec<PathBuf>,
+        exclude: &[String],
+        hidden: bool,
+        index: Option<PathBuf>,
+    ) -> Result<Self, String> {
+        let Some((first, rest)) = roots.split_first() else {
+            return Err(String::from("file_search needs at least one root."));
+        };
+
+        let mut excludes = GlobSetBuilder::new();
+        for glob in exclude {
+            excludes.add(Glob::new(glob).map_err(|e| format!("invalid exclude glob: {e}"))?);
+        }
+        let excludes = excludes
+            .build()
+            .map_err(|e| format!("invalid exclude glob: {e}"))?;
+
+        let mut walker = WalkBuilder::new(first);
+        for root in rest {
+            walker.add(root);
+        }
+        walker
+            .hidden(!hidden)
+            .filter_entry(move |entry| !excludes.is_match(entry.path()));
+
+        let nucleo = Nucleo::new(
+            nucleo::Config::DEFAULT.match_paths(),
+            Arc::new(|| {}),
+            None,
+            1,
+        );
+        let injector = nucleo.injector();
+        let walking = Arc::new(AtomicBool::new(true));
+
+        let indexed = index
+            .as_deref()
+            .and_then(|index| fs::read_to_string(index).ok());
+        if let Some(indexed) = &indexed {
+            for line in indexed.lines() {
+                push(&injector, PathBuf::from(line));
+            }
+        }
+
+        let done = walking.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let stop = cancelled.clone();
+        let (save, stream) = (index.is_some(), indexed.is_none());
+        thread::spawn(move || {
+            let found = Mutex::new(Vec::new());
+            walker.build_parallel().run(|| {
+                let injector = injector.clone();
+                let (found, stop) = (&found, &stop);
+                Box::new(move |entry| {
+                    if stop.load(Ordering::Relaxed) {
+                        return WalkState::Quit;
+                    }
+                    let Ok(entry) = entry else {
+                        return WalkState::Continue;
+                    };
+                    if entry.file_type().is_some_and(|t| t.is_file()) {
+                        let path = entry.into_path();
+                        if save {
+                            found.lock().expect("walker panicked").push(path.clone());
+                        }
+                        // when there's an index, its paths are already being searched.
+                        if stream {
+                            push(&injector, path);
+                        }
+                    }
+                    WalkState::Continue
+                })
+            });
+            done.store(false, Ordering::Relaxed);
+
+            // a walk that was stopped early would leave files out of the index.
+            if let Some(index) = index.filter(|_| !stop.load(Ordering::Relaxed)) {
+                let found = found.into_inner().expect("walker panicked");
+                if let Err(e) = save_index(&index, &found) {
+                    eprintln!("failed to save file index: {e}");
+                }
+            }
+        });
+
+        let mut search = Self {
+            nucleo,
+            query: String::new(),
+            walking,
+            was_walking: true,
+            cancelled,
+        };
+        search.tick();
+        Ok(search)
+    }
+
+    pub fn set_query(&mut self, query: &str) {
+        let append = query.starts_with(&self.query);
+        self.nucleo
+            .pattern
+            .reparse(0, query, CaseMatching::Smart, Normalization::Smart, append);
+        self.query = query.to_string();
+        self.tick();
+    }
+
+    /// updates the results, returning whether they, or whether the walk is done, changed.
+    pub fn tick(&mut self) -> bool {
+        let walking = self.is_walking();
+        let finished = self.was_walking && !walking;
+        self.was_walking = walking;
+        self.nucleo.tick(10).changed || finished
+    }
+
+    pub fn is_walking(&self) -> bool {
+        self.walking.load(Ordering::Relaxed)
+    }
+
+    /// the best `max` matches.
+    pub fn results(&self, max: usize) -> impl Iterator<Item = &Path> {
+        let snapshot = self.nucleo.snapshot();
+        let count = snapshot.matched_item_count().min(max as u32);
+        snapshot
+            .matched_items(..count)
+            .map(|item| item.data.as_path())
+    }
+}
+
+fn push(injector: &Injector<PathBuf>, path: PathBuf) {
+    injector.push(path, |path, columns| {
+        columns[0] = display_path(path).into();
+    });
+}
+
+/// shortens paths in the home directory to `~/...`.
+pub fn display_path(path: &Path) -> String {
+    static HOME: LazyLock<Option<PathBuf>> = LazyLock::new(dirs::home_dir);
+    match HOME.as_ref().and_then(|home| path.strip_prefix(home).ok()) {
+        Some(relative) => format!("~/{}", relative.to_string_lossy()),
+        None => path.to_string_lossy().to_string(),
+    }
+}
+
+fn save_index(index: &Path, paths: &[PathBuf]) -> std::io::Result<()> {
+    if let Some(parent) = index.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // written to a temporary file first, so a crash can't leave half an index.
+    let temp = index.with_extension("tmp");
+    let mut file = std::io::BufWriter::new(fs::File::create(&temp)?);
+    for path in paths {
+        file.write_all(path.as_os_str().as_encoded_bytes())?;
+        file.write_all(b"\n")?;
+    }
+    file.flush()?;
+    drop(file);
+    fs::rename(temp, index)
+}
@@ -0,0 +1,63 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::config::expand_home;
+
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+impl FileEntry {
+    pub fn is_hidden(&self) -> bool {
+        self.name.starts_with('.')
+    }
+
+    /// the name as it's shown, with a trailing `/` for directories.
+    pub fn display_name(&self) -> String {
+        if self.is_dir {
+            format!("{}/", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+impl AsRef<str> for FileEntry {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+/// the directory `dir` names when browsing from `root`. it's relative to `root`, unless it starts
+/// with `/` or `~`.
+pub fn resolve(root: &Path, dir: &str) -> PathBuf {
+    if dir.is_empty() {
+        root.to_path_buf()
+    } else {
+        root.join(expand_home(dir))
+    }
+}
+
+/// lists a directory, with directories first.
+pub fn read_dir(dir: &Path) -> Result<Vec<FileEntry>, String> {
+    let read =
+        fs::read_dir(dir).map_err(|e| format!("failed to read {}: {e}", dir.to_string_lossy()))?;
+    let mut entries: Vec<FileEntry> = read
+        .filter_map(Result::ok)
+        .map(|entry| FileEntry {
+            name: entry.file_name().to_string_lossy().to_string(),
+            // follows symlinks, so links to directories can be opened too.
+            is_dir: entry.path().is_dir(),
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    Ok(entries)
+}
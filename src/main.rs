@@ -1,17 +1,45 @@
-use std::{fs, io};
+use std::{
+    io,
+    path::Path,
+    process,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
 
 use clap::Parser;
-use config::Config;
+use config::{ActionKind, Config, Submenu};
+use watch::Watcher;
 
+mod bookmarks;
+mod calculator;
+mod check;
+mod clipboard;
 mod config;
+mod convert;
 #[cfg(feature = "launch")]
 mod desktop_entries;
+mod file_search;
+mod files;
+mod open;
+mod recent_files;
+mod ssh_hosts;
 mod state;
+mod template;
+mod unicode;
+mod watch;
+mod web_search;
+mod which;
+
+/// how often config files are checked for changes.
+const RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+/// how often results found in the background are checked for, while they're coming in.
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(clap::Parser)]
 #[command(version, about)]
 struct Cli {
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     config: Option<String>,
     #[arg(short, long = "var")]
     variables: Vec<String>,
@@ -19,82 +47,230 @@ struct Cli {
     max_items: Option<usize>,
     #[arg(long)]
     cold_run: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// check the config and every submenu it references for problems.
+    Check,
+    /// print a json schema for the config file format.
+    Schema {
+        /// print the schema for submenu files instead.
+        #[arg(long)]
+        submenu: bool,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    match try_make_config(&cli) {
-        Ok(mut config) => {
-            config
-                .variables
-                .extend(cli.variables.into_iter().filter_map(|mut a| {
-                    let index = a.find('=')?;
-                    let b = a[index + 1..].to_string();
-                    a.truncate(index);
-                    Some((a, b))
-                }));
-
-            let apps = if config.general.search_apps {
-                #[cfg(feature = "launch")]
-                {
-                    match desktop_entries::get_desktop_entries() {
-                        Ok(items) => Some(items),
-                        Err(e) => {
-                            println!(r#"{{"type":"error","message":"{e}"}}"#);
-                            return;
-                        }
-                    }
-                }
-                #[cfg(not(feature = "launch"))]
+    match cli.command {
+        Some(Command::Check) => {
+            let problems = check::check(Path::new(&config_path(&cli)));
+            if problems == 0 {
+                println!("no problems found.");
+            } else {
+                println!("{problems} problem(s) found.");
+                process::exit(1);
+            }
+            return;
+        }
+        Some(Command::Schema { submenu }) => {
+            let schema = if submenu {
+                schemars::schema_for!(Submenu)
+            } else {
+                let mut schema = schemars::schema_for!(Config);
+                // `include` is handled before the config is deserialized, so it isn't a field.
+                if let Some(properties) =
+                    schema.get_mut("properties").and_then(|p| p.as_object_mut())
                 {
-                    eprintln!(
-                        "superspace was not compiled with launcher support, but this config requires it. add use_launcher = false to the config to disable launcher support."
+                    properties.insert(
+                        String::from("include"),
+                        serde_json::json!({
+                            "description": "config files to merge before this one.",
+                            "type": "array",
+                            "items": { "type": "string" },
+                        }),
                     );
-                    return;
                 }
-            } else {
-                None
+                schema
             };
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&schema).expect("schemas are valid json")
+            );
+            return;
+        }
+        None => (),
+    }
 
-            let mut state = state::State::new(&config, apps.as_ref(), cli.cold_run, cli.max_items);
+    let (input_tx, input_rx) = mpsc::channel();
+    thread::spawn(move || {
+        loop {
             let mut api_input = String::new();
-
-            while !state.should_exit {
-                println!("{state}");
-                // dbg!(&state);
-
-                api_input.clear();
-                if let Err(_) = io::stdin().read_line(&mut api_input) {
+            match io::stdin().read_line(&mut api_input) {
+                Ok(0) => break,
+                Ok(_) => {
+                    if input_tx.send(api_input).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => {
                     eprintln!("failed to get input");
                     break;
                 }
-                match api_input.as_str() {
-                    "backspace\n" => state.process_backspace(),
-                    "enter\n" => state.process_enter(),
-                    _ => state.process_input(api_input.chars().next().unwrap()),
-                }
             }
         }
+    });
+
+    let mut config = match try_make_config(&cli) {
+        Ok(config) => config,
         Err(e) => {
-            println!(r#"{{"type":"error","message":"{e}"}}"#)
+            println!(r#"{{"type":"error","message":"{}"}}"#, state::sanitize(&e));
+            return;
+        }
+    };
+    // the character table takes a moment to build, so it's started before it's needed.
+    if config
+        .command
+        .values()
+        .any(|(cmd, _)| matches!(cmd.action.kind, ActionKind::Unicode { .. }))
+    {
+        unicode::preload();
+    }
+    let mut watcher = Watcher::default();
+    // the input and variables of the session before a reload, to pick up where it left off.
+    let mut restore: Option<(String, Vec<(String, String)>)> = None;
+
+    loop {
+        let apps = if config.general.search_apps {
+            #[cfg(feature = "launch")]
+            {
+                match desktop_entries::get_desktop_entries() {
+                    Ok(items) => Some(items),
+                    Err(e) => {
+                        println!(r#"{{"type":"error","message":"{}"}}"#, state::sanitize(&e));
+                        return;
+                    }
+                }
+            }
+            #[cfg(not(feature = "launch"))]
+            {
+                eprintln!(
+                    "superspace was not compiled with launcher support, but this config requires it. add use_launcher = false to the config to disable launcher support."
+                );
+                return;
+            }
+        } else {
+            None
+        };
+
+        let menus = state::Menus::default();
+        let mut state =
+            state::State::new(&config, apps.as_ref(), &menus, cli.cold_run, cli.max_items);
+        if let Some((input, variables)) = restore.take() {
+            state.restore(&input, variables);
+        }
+        watcher.changed(&config.sources);
+
+        // runs the session until it exits or the config changes on disk.
+        let reloaded = 'session: loop {
+            if state.should_exit {
+                if state.has_output() {
+                    println!("{state}");
+                }
+                break None;
+            }
+            println!("{state}");
+            // dbg!(&state);
+
+            let api_input = loop {
+                let timeout = if state.is_busy() {
+                    TICK_INTERVAL
+                } else {
+                    RELOAD_INTERVAL
+                };
+                match input_rx.recv_timeout(timeout) {
+                    Ok(api_input) => break Some(api_input),
+                    Err(RecvTimeoutError::Disconnected) => break 'session None,
+                    Err(RecvTimeoutError::Timeout) => {
+                        // a config that failed to load is tried again until it's fixed.
+                        if state.config_error().is_some()
+                            || watcher.changed(config.sources.iter().chain(state.menu_files()))
+                        {
+                            // the old config stays in use unless the new one loads.
+                            match try_make_config(&cli) {
+                                Ok(config) => {
+                                    break 'session Some((
+                                        config,
+                                        state.input().to_string(),
+                                        state.temp_variables(),
+                                    ));
+                                }
+                                Err(e) => {
+                                    if state.set_config_error(e) {
+                                        break None;
+                                    }
+                                }
+                            }
+                        }
+                        // results found in the background are shown without waiting for input.
+                        if state.tick() {
+                            break None;
+                        }
+                    }
+                }
+            };
+            let Some(api_input) = api_input else {
+                continue;
+            };
+            // each line of input is one of:
+            // - `backspace` or `enter`.
+            // - `up` or `down`, which move through web search suggestions.
+            // - `toggle_hidden`, which shows or hides hidden files when browsing files.
+            // - anything else, whose first character is typed.
+            match api_input.as_str() {
+                "backspace\n" => state.process_backspace(),
+                "enter\n" => state.process_enter(),
+                "toggle_hidden\n" => state.toggle_hidden(),
+                "up\n" => state.move_selection(-1),
+                "down\n" => state.move_selection(1),
+                _ => state.process_input(api_input.chars().next().unwrap()),
+            }
+        };
+
+        match reloaded {
+            Some((new_config, input, variables)) => {
+                config = new_config;
+                restore = Some((input, variables));
+            }
+            None => break,
         }
     }
 }
 
-fn try_make_config(cli: &Cli) -> Result<Config, String> {
-    let buf;
-    let path = if let Some(path) = &cli.config {
-        path
+fn config_path(cli: &Cli) -> String {
+    if let Some(path) = &cli.config {
+        path.clone()
     } else {
-        buf = format!(
+        format!(
             "{}/superspace/config.toml",
             dirs::config_dir()
                 .expect("no config directory")
                 .to_string_lossy()
-        );
-        &buf
-    };
-    let file = fs::read_to_string(path).map_err(|_| String::from("failed to find config file."))?;
-    toml::from_str::<Config>(&file).map_err(|e| e.message().to_string())
+        )
+    }
+}
+
+fn try_make_config(cli: &Cli) -> Result<Config, String> {
+    let mut config = Config::load(Path::new(&config_path(cli)))?;
+    config
+        .variables
+        .extend(cli.variables.iter().filter_map(|a| {
+            let (name, value) = a.split_once('=')?;
+            Some((name.to_string(), value.to_string()))
+        }));
+    Ok(config)
 }
@@ -0,0 +1,61 @@
+use std::path::Path;
+
+/// opens a url, or a path, with its default application.
+pub fn open(target: &str) -> Result<(), String> {
+    let uri = if has_scheme(target) {
+        target.to_string()
+    } else {
+        file_uri(Path::new(target))?
+    };
+
+    #[cfg(feature = "launch")]
+    {
+        use gio::{AppInfo, AppLaunchContext};
+
+        AppInfo::launch_default_for_uri(&uri, None::<&AppLaunchContext>)
+            .map_err(|e| format!("failed to open {target}: {e}"))
+    }
+    #[cfg(not(feature = "launch"))]
+    {
+        use std::{os::unix::process::CommandExt, process::Command};
+
+        Command::new("xdg-open")
+            .arg(&uri)
+            .process_group(0)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("failed to open {target}: {e}"))
+    }
+}
+
+/// whether `target` starts with a uri scheme, like `https:` or `mailto:`.
+fn has_scheme(target: &str) -> bool {
+    target.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+fn file_uri(path: &Path) -> Result<String, String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("failed to open {}: {e}", path.to_string_lossy()))?;
+    Ok(format!(
+        "file://{}",
+        percent_encoding::utf8_percent_encode(&path.to_string_lossy(), PATH_ENCODE_SET)
+    ))
+}
+
+const PATH_ENCODE_SET: &percent_encoding::AsciiSet = &percent_encoding::CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use percent_encoding::percent_decode_str;
+
+use crate::{
+    config::{ActionKind, ListItem},
+    file_search::display_path,
+};
+
+/// reads the files in `recently-used.xbel`, most recently visited first. each item opens its
+/// file with its default application.
+pub fn recent_files() -> Result<Vec<ListItem>, String> {
+    let path = dirs::data_dir()
+        .ok_or_else(|| String::from("no data directory."))?
+        .join("recently-used.xbel");
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("failed to read {}: {e}", path.to_string_lossy()))?;
+    let document = roxmltree::Document::parse(&text)
+        .map_err(|e| format!("failed to parse {}: {e}", path.to_string_lossy()))?;
+
+    let mut files: Vec<(&str, &str, PathBuf, Option<&str>)> = document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("bookmark"))
+        .filter_map(|bookmark| {
+            let href = bookmark.attribute("href")?;
+            let path = file_path(href)?;
+            if !path.exists() {
+                return None;
+            }
+            // timestamps are all written in the same iso 8601 format, so they sort as strings.
+            let visited = bookmark
+                .attribute("visited")
+                .or_else(|| bookmark.attribute("modified"))
+                .unwrap_or_default();
+            // the app that used the file last.
+            let app = bookmark
+                .descendants()
+                .filter(|node| node.has_tag_name("application"))
+                .max_by_key(|app| app.attribute("modified").unwrap_or_default())
+                .and_then(|app| app.attribute("name"));
+            Some((visited, href, path, app))
+        })
+        .collect();
+    files.sort_by(|a, b| b.0.cmp(a.0));
+
+    Ok(files
+        .into_iter()
+        .map(|(_, href, path, app)| ListItem {
+            name: display_path(&path),
+            description: app.map(str::to_string),
+            action: ActionKind::OpenUrl(href.to_string()).into(),
+            search: None,
+            variables: Vec::new(),
+        })
+        .collect())
+}
+
+/// the local path of a `file://` uri.
+fn file_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let path = percent_decode_str(path).decode_utf8().ok()?;
+    Some(PathBuf::from(&*path))
+}
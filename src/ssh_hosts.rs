@@ -0,0 +1,160 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use globset::Glob;
+
+use crate::config::{ActionKind, ExecOptions, ListItem};
+
+/// how deep `Include` directives are followed, like ssh itself.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// the hosts in `~/.ssh/config`, then the ones in `~/.ssh/known_hosts` that aren't in the
+/// config. each item runs `command` with `{{HOST}}` set to the host.
+pub fn hosts(command: &[String], options: &ExecOptions) -> Vec<ListItem> {
+    let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) else {
+        return Vec::new();
+    };
+
+    let mut hosts = Vec::new();
+    read_config(&ssh_dir.join("config"), &ssh_dir, 0, &mut hosts);
+    if let Ok(known_hosts) = fs::read_to_string(ssh_dir.join("known_hosts")) {
+        for (host, aliases) in known_hosts.lines().filter_map(parse_known_host) {
+            if !hosts.iter().any(|(h, _)| *h == host) {
+                hosts.push((host, aliases));
+            }
+        }
+    }
+
+    hosts
+        .into_iter()
+        .map(|(host, description)| ListItem {
+            action: ActionKind::Exec {
+                command: command.to_vec(),
+                options: options.clone(),
+            }
+            .into(),
+            variables: vec![("HOST", host.clone())],
+            name: host,
+            description,
+            search: None,
+        })
+        .collect()
+}
+
+/// adds the `Host` entries in an ssh config file to `hosts`, with their `HostName` as the
+/// description.
+fn read_config(
+    path: &Path,
+    ssh_dir: &Path,
+    depth: usize,
+    hosts: &mut Vec<(String, Option<String>)>,
+) {
+    let Ok(text) = fs::read_to_string(path) else {
+        return;
+    };
+    // the hosts added by the current `Host` line.
+    let mut block = hosts.len()..hosts.len();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, args) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or((line, ""));
+        let args = args.trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+
+        match keyword.to_lowercase().as_str() {
+            "host" => {
+                let start = hosts.len();
+                for host in args.split_whitespace() {
+                    // patterns match many hosts, so there's nothing to connect to.
+                    if host.contains(['*', '?', '!']) || hosts.iter().any(|(h, _)| h == host) {
+                        continue;
+                    }
+                    hosts.push((host.to_string(), None));
+                }
+                block = start..hosts.len();
+            }
+            "match" => block = hosts.len()..hosts.len(),
+            "hostname" => {
+                for (_, description) in &mut hosts[block.clone()] {
+                    description.get_or_insert_with(|| args.to_string());
+                }
+            }
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in args.split_whitespace() {
+                    for include in expand_include(pattern, ssh_dir) {
+                        read_config(&include, ssh_dir, depth + 1, hosts);
+                    }
+                }
+                block = hosts.len()..hosts.len();
+            }
+            _ => (),
+        }
+    }
+}
+
+/// the files an `Include` pattern refers to. relative paths are relative to `~/.ssh`.
+fn expand_include(pattern: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = match pattern.strip_prefix("~/") {
+        Some(rest) => match dirs::home_dir() {
+            Some(home) => home.join(rest),
+            None => return Vec::new(),
+        },
+        None => ssh_dir.join(pattern),
+    };
+    if !pattern.contains(['*', '?', '[']) {
+        return vec![path];
+    }
+
+    // only the file name is matched, which covers the usual `config.d/*`.
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let Ok(glob) = Glob::new(&name.to_string_lossy()) else {
+        return Vec::new();
+    };
+    let glob = glob.compile_matcher();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| glob.is_match(entry.file_name()))
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// the first host name on a `known_hosts` line, with the others as a description.
+fn parse_known_host(line: &str) -> Option<(String, Option<String>)> {
+    let line = line.trim();
+    // markers are for certificate authorities and revoked keys, not hosts.
+    if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+        return None;
+    }
+    let names = line.split_whitespace().next()?;
+    // hashed names can't be turned back into hosts.
+    if names.starts_with('|') {
+        return None;
+    }
+
+    let mut names = names
+        .split(',')
+        .filter(|name| !name.contains(['*', '?', '!']))
+        .map(|name| {
+            // non-standard ports are written as `[host]:port`, which ssh only takes as a url.
+            if name.starts_with('[') {
+                format!("ssh://{name}")
+            } else {
+                name.to_string()
+            }
+        });
+    let host = names.next()?;
+    let aliases: Vec<String> = names.collect();
+    Some((host, (!aliases.is_empty()).then(|| aliases.join(", "))))
+}
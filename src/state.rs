@@ -4,18 +4,59 @@ use std::{
     env::vars_os,
     ffi::{OsStr, OsString},
     fmt::Display,
-    fs,
-    process::{Command, Stdio},
+    io::Read,
+    mem,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    ptr, thread,
+    time::{Duration, Instant},
 };
 
-use lazy_regex::regex_replace_all;
+use lazy_regex::{Regex, regex_replace_all};
+
+use typed_arena::Arena;
 
 use nucleo::{
     Matcher,
     pattern::{AtomKind, CaseMatching, Normalization, Pattern},
 };
 
-use crate::config::{Action, Config, ListItem, OutputMode, Submenu, UserCommand};
+use crate::{
+    bookmarks, calculator, clipboard,
+    config::{
+        Action, ActionKind, CommandLine, Config, ExecOptions, FormField, ListItem, OnEnter,
+        OutputMode, Submenu, UnitDefinition, UserCommand, describe_error, expand_home,
+    },
+    convert,
+    file_search::{self, FileSearch},
+    files::{self, FileEntry},
+    open, recent_files, ssh_hosts,
+    template::{self, Escape},
+    unicode,
+    web_search::Suggestions,
+};
+
+/// how long a validation command can take when the prompt doesn't set `timeout_ms`, since
+/// input waits for it.
+const VALIDATE_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Evaluator<'conf> {
+    Calculator,
+    Convert(&'conf [UnitDefinition]),
+}
+
+impl Evaluator<'_> {
+    /// returns the result as it's shown, and passed to commands as `{{RESULT}}`.
+    fn evaluate(&self, input: &str) -> Result<String, String> {
+        match self {
+            Evaluator::Calculator => calculator::evaluate(input).map(calculator::format_number),
+            Evaluator::Convert(units) => convert::convert(input, units)
+                .map(|(value, unit)| format!("{} {unit}", calculator::format_number(value))),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub(crate) enum StateEnum<'conf> {
@@ -25,17 +66,121 @@ pub(crate) enum StateEnum<'conf> {
     },
     Prompt {
         prefix_len: usize,
-        command: &'conf Vec<String>,
+        command: &'conf CommandLine,
         output_mode: OutputMode,
+        options: &'conf ExecOptions,
+        validate: Option<&'conf Regex>,
+        validate_command: Option<&'conf Vec<String>>,
+        /// the validation message, if the current input is invalid.
+        validation: Option<String>,
+        on_enter: OnEnter,
     },
     List {
         prefix_len: usize,
         items: &'conf Vec<ListItem>,
         filtered: Option<Vec<(&'conf ListItem, u32)>>,
+        on_enter: OnEnter,
+        /// how many items are shown when there's no `--max-items`. generated lists can be huge,
+        /// so they're cut off.
+        limit: usize,
+    },
+    Form {
+        prefix_len: usize,
+        fields: &'conf [FormField],
+        index: usize,
+        filtered: Option<Vec<(&'conf String, u32)>>,
+        error: Option<String>,
+        command: &'conf CommandLine,
+        options: &'conf ExecOptions,
+        /// the values the entered fields replaced, put back when going back a field or once the
+        /// command has run.
+        saved: Vec<(&'conf str, Option<Cow<'conf, str>>)>,
+    },
+    /// a prompt that shows the evaluated input as it's typed.
+    Evaluator {
+        prefix_len: usize,
+        evaluator: Evaluator<'conf>,
+        command: Option<&'conf Vec<String>>,
+        options: &'conf ExecOptions,
+        on_enter: OnEnter,
+    },
+    Confirm {
+        message: &'conf str,
+        action: &'conf ActionKind,
+        previous: Box<StateEnum<'conf>>,
+    },
+    /// browses a directory. the input after the prefix is a path relative to `root`, or from the
+    /// root or home directory if it starts with `/` or `~`. the part after the last `/` filters
+    /// the directory.
+    Files {
+        prefix_len: usize,
+        root: PathBuf,
+        /// the directory being shown, as typed. see `files::resolve`.
+        dir: String,
+        entries: Result<Vec<FileEntry>, String>,
+        filtered: Option<Vec<FileEntry>>,
+        hidden: bool,
+        command: Option<&'conf Vec<String>>,
+        options: &'conf ExecOptions,
+    },
+    FileSearch {
+        prefix_len: usize,
+        search: Box<FileSearch>,
+        command: Option<&'conf Vec<String>>,
+        options: &'conf ExecOptions,
+    },
+    WebSearch {
+        prefix_len: usize,
+        url: &'conf str,
+        /// the suggestion command, and its latest results.
+        suggest: Option<(&'conf Vec<String>, Suggestions)>,
+        /// the suggestion that enter searches for, instead of the input.
+        selected: Option<usize>,
     },
+    /// a result to print before exiting.
+    Output(String),
     Error(String),
 }
 
+impl StateEnum<'_> {
+    /// the length of the command prefix in front of the input, for states that take input.
+    fn prefix_len(&self) -> Option<usize> {
+        match self {
+            StateEnum::Prompt { prefix_len, .. }
+            | StateEnum::List { prefix_len, .. }
+            | StateEnum::Form { prefix_len, .. }
+            | StateEnum::Evaluator { prefix_len, .. }
+            | StateEnum::Files { prefix_len, .. }
+            | StateEnum::FileSearch { prefix_len, .. }
+            | StateEnum::WebSearch { prefix_len, .. } => Some(*prefix_len),
+            StateEnum::MainMenu { .. }
+            | StateEnum::Confirm { .. }
+            | StateEnum::Output(_)
+            | StateEnum::Error(_) => None,
+        }
+    }
+}
+
+/// owns the submenus loaded from files and the lists generated during a session, and the names
+/// of variables carried over from before a reload.
+#[derive(Default)]
+pub(crate) struct Menus {
+    submenus: Arena<Submenu>,
+    lists: Arena<Vec<ListItem>>,
+    names: Arena<String>,
+}
+
+impl std::fmt::Debug for Menus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Menus {{ submenus: {}, lists: {} }}",
+            self.submenus.len(),
+            self.lists.len()
+        )
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct State<'conf> {
     state_enum: StateEnum<'conf>,
@@ -47,9 +192,19 @@ pub(crate) struct State<'conf> {
     pub should_exit: bool,
     cold_run: bool,
     max_items: Option<usize>,
+    /// set while the input is replayed after a reload, so nothing is run again.
+    restoring: bool,
+    /// why the config on disk failed to reload, while the old one stays in use.
+    config_error: Option<String>,
 
     temp_variables: HashMap<&'conf str, Cow<'conf, str>>,
-    loaded_menus: HashMap<&'conf String, &'static Submenu>,
+    loaded_menus: HashMap<&'conf str, &'conf Submenu>,
+    /// submenus loaded from files live here, so they're freed when the session ends.
+    menus: &'conf Menus,
+    /// every submenu file that has been read, so they can be watched for changes.
+    menu_files: Vec<PathBuf>,
+    /// the items of built-in list actions, which are only generated once.
+    generated: Vec<(&'conf ActionKind, &'conf Vec<ListItem>)>,
     apps: Option<&'conf Vec<ListItem>>,
     config: &'conf Config,
 }
@@ -58,6 +213,7 @@ impl<'conf> State<'conf> {
     pub(crate) fn new(
         config: &'conf Config,
         apps: Option<&'conf Vec<ListItem>>,
+        menus: &'conf Menus,
         cold_run: bool,
         max_items: Option<usize>,
     ) -> Self {
@@ -74,9 +230,14 @@ impl<'conf> State<'conf> {
             should_exit: false,
             cold_run,
             max_items,
+            restoring: false,
+            config_error: None,
 
             temp_variables: HashMap::new(),
             loaded_menus: HashMap::new(),
+            menus,
+            menu_files: Vec::new(),
+            generated: Vec::new(),
             apps,
             config,
         }
@@ -122,6 +283,7 @@ impl<'conf> State<'conf> {
                 items,
                 filtered,
                 prefix_len,
+                ..
             } => {
                 *filtered = Some(State::get_matches(
                     &self.input[*prefix_len..],
@@ -129,14 +291,110 @@ impl<'conf> State<'conf> {
                     *items,
                 ))
             }
-            StateEnum::Prompt { .. } | StateEnum::Error(_) => (),
+            StateEnum::Form {
+                prefix_len,
+                fields,
+                index,
+                filtered,
+                error,
+                ..
+            } => {
+                *error = None;
+                if let Some(choices) = &fields[*index].choices {
+                    *filtered = Some(State::get_matches(
+                        &self.input[*prefix_len..],
+                        &mut self.matcher,
+                        choices,
+                    ));
+                }
+            }
+            StateEnum::Prompt {
+                prefix_len,
+                options,
+                validate,
+                validate_command,
+                validation,
+                ..
+            } => {
+                *validation = State::validate_input(
+                    self.config,
+                    &self.temp_variables,
+                    &self.input[*prefix_len..],
+                    *validate,
+                    *validate_command,
+                    options,
+                );
+            }
+            StateEnum::Evaluator { .. } => (),
+            StateEnum::WebSearch { .. } => self.request_suggestions(),
+            StateEnum::FileSearch {
+                prefix_len, search, ..
+            } => search.set_query(&self.input[*prefix_len..]),
+            StateEnum::Files {
+                prefix_len,
+                entries,
+                filtered,
+                ..
+            } => {
+                if added_char == '/' {
+                    self.input.pop();
+                    // a `/` opens the directory that was typed, or the best match.
+                    let query_start =
+                        *prefix_len + self.input[*prefix_len..].rfind('/').map_or(0, |i| i + 1);
+                    let query = &self.input[query_start..];
+                    // a leading `/` or `~/` starts from the root or home directory.
+                    let from_start =
+                        query_start == *prefix_len && (query.is_empty() || query == "~");
+                    let exact = from_start
+                        || query == ".."
+                        || entries
+                            .as_ref()
+                            .is_ok_and(|e| e.iter().any(|e| e.is_dir && e.name == query));
+                    let name = if exact {
+                        query.to_string()
+                    } else {
+                        match filtered.as_ref().and_then(|f| f.first()) {
+                            Some(entry) if entry.is_dir => entry.name.clone(),
+                            _ => return,
+                        }
+                    };
+                    self.input.truncate(query_start);
+                    self.input.push_str(&name);
+                    self.input.push('/');
+                }
+                self.refresh_files();
+            }
+            StateEnum::Confirm { action, .. } => {
+                self.input.pop();
+                match added_char {
+                    'y' | 'Y' => {
+                        let action = *action;
+                        self.run_action(action);
+                    }
+                    'n' | 'N' => self.cancel_confirm(),
+                    _ => (),
+                }
+            }
+            StateEnum::Output(_) | StateEnum::Error(_) => (),
         }
     }
 
     pub(crate) fn process_backspace(&mut self) {
+        if let StateEnum::Confirm { .. } = self.state_enum {
+            self.cancel_confirm();
+            return;
+        }
         if self.input.pop().is_none() {
             return;
         }
+        // erasing the prefix leaves the state, except in forms, which go back a field instead.
+        if let Some(prefix_len) = self.state_enum.prefix_len()
+            && self.input.len() < prefix_len
+            && !matches!(self.state_enum, StateEnum::Form { index: 1.., .. })
+        {
+            self.return_to_main_menu();
+            return;
+        }
 
         match &mut self.state_enum {
             StateEnum::MainMenu { items, filtered } => {
@@ -156,16 +414,9 @@ impl<'conf> State<'conf> {
                 items,
                 filtered,
                 prefix_len,
+                ..
             } => {
-                if self.input.len() < *prefix_len {
-                    self.state_enum = StateEnum::MainMenu {
-                        items: &self.config.command,
-                        filtered: Some(State::get_prefix_matches(
-                            &self.input,
-                            self.config.command.iter(),
-                        )),
-                    }
-                } else if self.input.len() > *prefix_len {
+                if self.input.len() > *prefix_len {
                     *filtered = Some(State::get_matches(
                         &self.input[*prefix_len..],
                         &mut self.matcher,
@@ -175,19 +426,238 @@ impl<'conf> State<'conf> {
                     *filtered = None;
                 }
             }
-            StateEnum::Prompt { prefix_len, .. } => {
+            StateEnum::Prompt {
+                prefix_len,
+                options,
+                validate,
+                validate_command,
+                validation,
+                ..
+            } => {
+                *validation = State::validate_input(
+                    self.config,
+                    &self.temp_variables,
+                    &self.input[*prefix_len..],
+                    *validate,
+                    *validate_command,
+                    options,
+                );
+            }
+            StateEnum::Form {
+                prefix_len,
+                fields,
+                index,
+                filtered,
+                error,
+                saved,
+                ..
+            } => {
+                *error = None;
                 if self.input.len() < *prefix_len {
-                    self.state_enum = StateEnum::MainMenu {
-                        items: &self.config.command,
-                        filtered: Some(State::get_prefix_matches(
-                            &self.input,
-                            self.config.command.iter(),
-                        )),
+                    // go back to the previous field, with the value it was given.
+                    *index -= 1;
+                    self.input.push(' ');
+                    if let Some(value) = self.temp_variables.get(fields[*index].name.as_str()) {
+                        self.input.push_str(value);
                     }
+                    match saved.pop() {
+                        Some((name, Some(old))) => self.temp_variables.insert(name, old),
+                        Some((name, None)) => self.temp_variables.remove(name),
+                        None => None,
+                    };
+                    *filtered = None;
+                } else if let Some(choices) = &fields[*index].choices {
+                    *filtered = Some(State::get_matches(
+                        &self.input[*prefix_len..],
+                        &mut self.matcher,
+                        choices,
+                    ));
+                }
+            }
+            StateEnum::Files { .. } => self.refresh_files(),
+            StateEnum::FileSearch {
+                prefix_len, search, ..
+            } => search.set_query(&self.input[*prefix_len..]),
+            StateEnum::WebSearch { .. } => self.request_suggestions(),
+            StateEnum::Evaluator { .. }
+            | StateEnum::Confirm { .. }
+            | StateEnum::Output(_)
+            | StateEnum::Error(_) => (),
+        }
+    }
+
+    pub(crate) fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// the variables set during the session, by `set_variable`, sequences and the like.
+    pub(crate) fn temp_variables(&self) -> Vec<(String, String)> {
+        self.temp_variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// sets `variables` and types `input` again after the config is reloaded, to get back to the
+    /// state it led to. if that would run something, or ends up somewhere else, the input is kept
+    /// in the main menu.
+    pub(crate) fn restore(&mut self, input: &str, variables: Vec<(String, String)>) {
+        for (name, value) in variables {
+            let name: &'conf str = self.menus.names.alloc(name);
+            self.temp_variables.insert(name, Cow::Owned(value));
+        }
+        self.restoring = true;
+        for c in input.chars() {
+            self.process_input(c);
+            if !self.restoring {
+                break;
+            }
+        }
+        if !self.restoring || self.input != input || matches!(self.state_enum, StateEnum::Error(_))
+        {
+            self.input = input.to_string();
+            self.return_to_main_menu();
+        }
+        self.restoring = false;
+    }
+
+    /// shows why the config failed to reload, returning whether the message changed.
+    pub(crate) fn set_config_error(&mut self, error: String) -> bool {
+        let changed = self.config_error.as_ref() != Some(&error);
+        self.config_error = Some(error);
+        changed
+    }
+
+    pub(crate) fn config_error(&self) -> Option<&str> {
+        self.config_error.as_deref()
+    }
+
+    /// goes back to the main menu, filtered by the input.
+    fn return_to_main_menu(&mut self) {
+        self.state_enum = StateEnum::MainMenu {
+            items: &self.config.command,
+            filtered: Some(State::get_prefix_matches(
+                &self.input,
+                self.config.command.iter(),
+            )),
+        };
+    }
+
+    /// updates results that are found in the background, returning whether they changed.
+    pub(crate) fn tick(&mut self) -> bool {
+        match &mut self.state_enum {
+            StateEnum::FileSearch { search, .. } => search.tick(),
+            StateEnum::WebSearch {
+                suggest: Some((_, suggestions)),
+                selected,
+                ..
+            } => {
+                let changed = suggestions.tick();
+                if changed {
+                    *selected = None;
                 }
+                changed
             }
-            StateEnum::Error(_) => (),
+            _ => false,
+        }
+    }
+
+    /// whether results are still being found in the background.
+    pub(crate) fn is_busy(&self) -> bool {
+        match &self.state_enum {
+            StateEnum::FileSearch { search, .. } => search.is_walking(),
+            StateEnum::WebSearch {
+                suggest: Some((_, suggestions)),
+                ..
+            } => suggestions.is_busy(),
+            _ => false,
+        }
+    }
+
+    /// moves the selection through the web search suggestions. moving up past the first one
+    /// selects the input again.
+    pub(crate) fn move_selection(&mut self, offset: isize) {
+        let StateEnum::WebSearch {
+            suggest: Some((_, suggestions)),
+            selected,
+            ..
+        } = &mut self.state_enum
+        else {
+            return;
+        };
+        let count = suggestions
+            .suggestions()
+            .len()
+            .min(self.max_items.unwrap_or(usize::MAX));
+        // the input is at -1.
+        let index = selected.map_or(-1, |i| i as isize) + offset;
+        *selected = (count > 0 && index >= 0).then(|| (index as usize).min(count - 1));
+    }
+
+    /// runs the suggestion command for the current web search input.
+    fn request_suggestions(&mut self) {
+        let StateEnum::WebSearch {
+            prefix_len,
+            suggest: Some((command, suggestions)),
+            selected,
+            ..
+        } = &mut self.state_enum
+        else {
+            return;
+        };
+        *selected = None;
+        let query = &self.input[*prefix_len..];
+        let mut temp_vars = self.temp_variables.clone();
+        temp_vars.insert("INPUT", Cow::Owned(query.to_string()));
+        match State::create_argv(self.config, &temp_vars, command) {
+            Ok(argv) => suggestions.request(query, argv.into_iter().map(Cow::into_owned).collect()),
+            Err(e) => self.state_enum = StateEnum::Error(e),
+        }
+    }
+
+    /// shows or hides hidden files in the file browser.
+    pub(crate) fn toggle_hidden(&mut self) {
+        if let StateEnum::Files { hidden, .. } = &mut self.state_enum {
+            *hidden = !*hidden;
+            self.refresh_files();
+        }
+    }
+
+    /// re-reads the directory if the input moved to another one, and filters it.
+    fn refresh_files(&mut self) {
+        let StateEnum::Files {
+            prefix_len,
+            root,
+            dir,
+            entries,
+            filtered,
+            hidden,
+            ..
+        } = &mut self.state_enum
+        else {
+            return;
+        };
+        let path = &self.input[*prefix_len..];
+        let (new_dir, query) = match path.rfind('/') {
+            // the root directory keeps its `/`.
+            Some(0) => path.split_at(1),
+            Some(i) => (&path[..i], &path[i + 1..]),
+            None => ("", path),
+        };
+        if new_dir != dir {
+            *dir = new_dir.to_string();
+            *entries = files::read_dir(&files::resolve(root, dir));
         }
+
+        *filtered = match entries {
+            Ok(entries) if !query.is_empty() => {
+                let show_hidden = *hidden || query.starts_with('.');
+                let visible = entries.iter().filter(|e| show_hidden || !e.is_hidden());
+                let matches = State::get_matches(query, &mut self.matcher, visible);
+                Some(matches.into_iter().map(|(e, _)| e.clone()).collect())
+            }
+            _ => None,
+        };
     }
 
     pub(crate) fn process_enter(&mut self) {
@@ -200,10 +670,7 @@ impl<'conf> State<'conf> {
                             .get(prefix)
                             .expect("filtered results should be in map");
 
-                        if matches!(
-                            cmd.action,
-                            Action::List { .. } | Action::Prompt { .. } | Action::ListApplications
-                        ) {
+                        if cmd.action.waits_for_input() {
                             self.input = prefix.clone();
                             self.input.push(' ');
                         }
@@ -219,7 +686,9 @@ impl<'conf> State<'conf> {
             StateEnum::List {
                 items,
                 filtered,
-                prefix_len: _,
+                on_enter,
+                prefix_len,
+                ..
             } => {
                 let item = if let Some((item, _)) = filtered.as_ref().and_then(|f| f.first()) {
                     *item
@@ -229,25 +698,51 @@ impl<'conf> State<'conf> {
                         None => return,
                     }
                 };
+                if *on_enter == OnEnter::Copy {
+                    self.copy(&item.name);
+                    return;
+                }
 
-                if matches!(
-                    item.action,
-                    Action::List { .. } | Action::Prompt { .. } | Action::ListApplications
-                ) {
-                    self.input
-                        .truncate(self.input.find(' ').expect("list must have a prefix"));
+                if item.action.waits_for_input() {
+                    self.input.truncate(*prefix_len);
                     self.input.push_str(&item.name);
                     self.input.push(' ');
                 }
 
+                let old = self.set_variables(
+                    item.variables
+                        .iter()
+                        .map(|(name, value)| (*name, Cow::Borrowed(value.as_str()))),
+                );
                 self.run_cmd(&item.action);
+                // a confirmed action runs later, so it still needs them.
+                if !matches!(self.state_enum, StateEnum::Confirm { .. }) {
+                    self.restore_variables(old);
+                }
             }
             StateEnum::Prompt {
                 command,
                 prefix_len,
+                options,
+                validate,
+                validate_command,
+                validation,
+                on_enter,
                 ..
             } => {
-                let command = *command;
+                let (command, options, on_enter) = (*command, *options, *on_enter);
+
+                *validation = State::validate_input(
+                    self.config,
+                    &self.temp_variables,
+                    &self.input[*prefix_len..],
+                    *validate,
+                    *validate_command,
+                    options,
+                );
+                if validation.is_some() {
+                    return;
+                }
 
                 let old_input = self
                     .temp_variables
@@ -255,19 +750,248 @@ impl<'conf> State<'conf> {
                 if self.cold_run {
                     dbg!(command, &self.config.variables, &self.temp_variables);
                     self.should_exit = true;
+                } else if on_enter == OnEnter::Copy {
+                    match State::create_command_argv(self.config, &self.temp_variables, command)
+                        .and_then(|argv| self.capture_trimmed(argv, options))
+                    {
+                        Ok(output) => self.copy(&output),
+                        Err(e) => self.state_enum = StateEnum::Error(e),
+                    }
                 } else {
-                    self.exec(command);
+                    match State::create_command_argv(self.config, &self.temp_variables, command) {
+                        Ok(argv) => self.exec(argv, options),
+                        Err(e) => self.state_enum = StateEnum::Error(e),
+                    }
                 }
                 if let Some(old) = old_input {
                     self.temp_variables.insert("INPUT", old);
                 }
             }
-            StateEnum::Error(_) => {
+            StateEnum::Form {
+                prefix_len,
+                fields,
+                index,
+                filtered,
+                error,
+                command,
+                options,
+                saved,
+            } => {
+                let field = &fields[*index];
+                let typed = &self.input[*prefix_len..];
+                let value = match &field.choices {
+                    _ if typed.is_empty() && field.default.is_some() => {
+                        field.default.clone().expect("checked above")
+                    }
+                    Some(choices) => {
+                        let choice = match filtered {
+                            Some(f) => f.first().map(|(c, _)| *c),
+                            None => choices.first(),
+                        };
+                        match choice {
+                            Some(c) => c.clone(),
+                            None => {
+                                *error = Some(String::from("no matching choice."));
+                                return;
+                            }
+                        }
+                    }
+                    None => typed.to_string(),
+                };
+                if field
+                    .validate
+                    .as_ref()
+                    .is_some_and(|re| !re.is_match(&value))
+                {
+                    *error = Some(format!("'{value}' is not a valid {}.", field.name));
+                    return;
+                }
+
+                let name = field.name.as_str();
+                saved.push((name, self.temp_variables.insert(name, Cow::Owned(value))));
+                self.input.truncate(*prefix_len);
+                if *index + 1 < fields.len() {
+                    *index += 1;
+                    *filtered = None;
+                    return;
+                }
+
+                let (command, options, saved) = (*command, *options, mem::take(saved));
+                if self.cold_run {
+                    dbg!(command, &self.config.variables, &self.temp_variables);
+                    self.should_exit = true;
+                } else {
+                    match State::create_command_argv(self.config, &self.temp_variables, command) {
+                        Ok(argv) => self.exec(argv, options),
+                        Err(e) => self.state_enum = StateEnum::Error(e),
+                    }
+                }
+                self.restore_variables(saved);
+            }
+            // only `y` runs the action, so a habitual enter can't.
+            StateEnum::Confirm { .. } => self.cancel_confirm(),
+            StateEnum::Evaluator {
+                prefix_len,
+                evaluator,
+                command,
+                options,
+                on_enter,
+            } => {
+                let Ok(result) = evaluator.evaluate(&self.input[*prefix_len..]) else {
+                    return;
+                };
+                match *command {
+                    _ if *on_enter == OnEnter::Copy => self.copy(&result),
+                    None => {
+                        self.state_enum = StateEnum::Output(result);
+                        self.should_exit = true;
+                    }
+                    Some(command) => {
+                        let options = *options;
+                        self.temp_variables
+                            .insert("INPUT", Cow::Owned(self.input[*prefix_len..].to_string()));
+                        self.temp_variables.insert("RESULT", Cow::Owned(result));
+                        if self.cold_run {
+                            dbg!(command, &self.config.variables, &self.temp_variables);
+                            self.should_exit = true;
+                        } else {
+                            match State::create_argv(self.config, &self.temp_variables, command) {
+                                Ok(argv) => self.exec(argv, options),
+                                Err(e) => self.state_enum = StateEnum::Error(e),
+                            }
+                        }
+                    }
+                }
+            }
+            StateEnum::Files {
+                prefix_len,
+                root,
+                dir,
+                filtered,
+                command,
+                options,
+                ..
+            } => {
+                let query = self.input[*prefix_len..]
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default();
+                // with no input, the directory itself is opened.
+                let dir = files::resolve(root, dir);
+                let path = if query.is_empty() {
+                    dir
+                } else {
+                    match filtered.as_ref().and_then(|f| f.first()) {
+                        Some(entry) => dir.join(&entry.name),
+                        None => return,
+                    }
+                };
+                let (command, options) = (*command, *options);
+                self.open_path(&path, command, options);
+            }
+            StateEnum::FileSearch {
+                search,
+                command,
+                options,
+                ..
+            } => {
+                let Some(path) = search.results(1).next().map(Path::to_owned) else {
+                    return;
+                };
+                let (command, options) = (*command, *options);
+                self.open_path(&path, command, options);
+            }
+            StateEnum::WebSearch {
+                prefix_len,
+                url,
+                suggest,
+                selected,
+            } => {
+                let query = match (suggest, selected) {
+                    (Some((_, suggestions)), Some(i)) => &suggestions.suggestions()[*i],
+                    _ => &self.input[*prefix_len..],
+                };
+                if query.trim().is_empty() {
+                    return;
+                }
+                let url = *url;
+                let old_input = self
+                    .temp_variables
+                    .insert("INPUT", Cow::Owned(query.to_string()));
+                match State::substitute(self.config, &self.temp_variables, url, Escape::Url) {
+                    Ok(url) => {
+                        let url = url.into_owned();
+                        self.open(&url);
+                    }
+                    Err(e) => self.state_enum = StateEnum::Error(e),
+                }
+                match old_input {
+                    Some(old) => self.temp_variables.insert("INPUT", old),
+                    None => self.temp_variables.remove("INPUT"),
+                };
+            }
+            StateEnum::Output(_) | StateEnum::Error(_) => {
                 self.should_exit = true;
             }
         }
     }
 
+    /// runs `command` with `{{PATH}}`, or opens the path with its default application.
+    fn open_path(
+        &mut self,
+        path: &Path,
+        command: Option<&'conf Vec<String>>,
+        options: &'conf ExecOptions,
+    ) {
+        let path = path.to_string_lossy().to_string();
+        match command {
+            Some(command) => {
+                self.temp_variables.insert("PATH", Cow::Owned(path));
+                if self.cold_run {
+                    dbg!(command, &self.config.variables, &self.temp_variables);
+                    self.should_exit = true;
+                } else {
+                    match State::create_argv(self.config, &self.temp_variables, command) {
+                        Ok(argv) => self.exec(argv, options),
+                        Err(e) => self.state_enum = StateEnum::Error(e),
+                    }
+                }
+            }
+            None => self.open(&path),
+        }
+    }
+
+    /// opens a file or url with its default application and exits.
+    fn open(&mut self, target: &str) {
+        if self.cold_run {
+            dbg!(target);
+            self.should_exit = true;
+            return;
+        }
+        match open::open(target) {
+            Ok(()) => self.should_exit = true,
+            Err(e) => self.state_enum = StateEnum::Error(e),
+        }
+    }
+
+    /// puts `text` on the clipboard and exits.
+    fn copy(&mut self, text: &str) {
+        if self.cold_run {
+            dbg!(text);
+            self.should_exit = true;
+            return;
+        }
+        match clipboard::copy(text, self.config.general.clipboard.as_deref()) {
+            Ok(()) => self.should_exit = true,
+            Err(e) => self.state_enum = StateEnum::Error(e),
+        }
+    }
+
+    /// whether the session ended with a result that should be printed.
+    pub(crate) fn has_output(&self) -> bool {
+        matches!(self.state_enum, StateEnum::Output(_))
+    }
+
     fn get_matches<'a, T: AsRef<str>>(
         input: &str,
         matcher: &mut Matcher,
@@ -295,41 +1019,318 @@ impl<'conf> State<'conf> {
     }
 
     fn run_cmd(&mut self, action: &'conf Action) {
+        if self.restoring
+            && action.confirm.is_none()
+            && !action.kind.takes_input()
+            && !matches!(action.kind, ActionKind::Submenu { .. })
+        {
+            self.restoring = false;
+            return;
+        }
+        if let Some(message) = &action.confirm {
+            let previous = mem::replace(&mut self.state_enum, StateEnum::Error(String::new()));
+            self.state_enum = StateEnum::Confirm {
+                message,
+                action: &action.kind,
+                previous: Box::new(previous),
+            };
+        } else {
+            self.run_action(&action.kind);
+        }
+    }
+
+    /// sets temporary variables, returning the values they replaced for `restore_variables`.
+    fn set_variables(
+        &mut self,
+        variables: impl IntoIterator<Item = (&'conf str, Cow<'conf, str>)>,
+    ) -> Vec<(&'conf str, Option<Cow<'conf, str>>)> {
+        variables
+            .into_iter()
+            .map(|(name, value)| (name, self.temp_variables.insert(name, value)))
+            .collect()
+    }
+
+    fn restore_variables(&mut self, old: Vec<(&'conf str, Option<Cow<'conf, str>>)>) {
+        for (name, value) in old.into_iter().rev() {
+            match value {
+                Some(value) => self.temp_variables.insert(name, value),
+                None => self.temp_variables.remove(name),
+            };
+        }
+    }
+
+    fn cancel_confirm(&mut self) {
+        let StateEnum::Confirm { previous, .. } =
+            mem::replace(&mut self.state_enum, StateEnum::Error(String::new()))
+        else {
+            return;
+        };
+        self.state_enum = *previous;
+        if let StateEnum::MainMenu { items, filtered } = &mut self.state_enum {
+            if self.input.ends_with(' ') {
+                self.input.pop();
+            }
+            *filtered =
+                (!self.input.is_empty()).then(|| State::get_prefix_matches(&self.input, *items));
+        }
+    }
+
+    fn run_action(&mut self, action: &'conf ActionKind) {
         match action {
-            Action::ListApplications => {
+            ActionKind::ListApplications => {
                 self.state_enum = if let Some(items) = &self.apps {
                     StateEnum::List {
                         prefix_len: self.input.len(),
                         items: *items,
                         filtered: None,
+                        on_enter: OnEnter::Run,
+                        limit: usize::MAX,
                     }
                 } else {
                     StateEnum::Error(String::from("applications are disabled in the config."))
                 }
             }
-            Action::List { items } => {
+            ActionKind::List { items, on_enter } => {
                 self.state_enum = StateEnum::List {
                     prefix_len: self.input.len(),
                     items,
                     filtered: None,
+                    on_enter: *on_enter,
+                    limit: usize::MAX,
                 }
             }
-            Action::Prompt { command, output } => {
+            ActionKind::Prompt {
+                command,
+                output,
+                validate,
+                validate_command,
+                on_enter,
+                options,
+            } => {
+                // the empty input is checked too, so the first frame says whether it's valid.
+                let validation = State::validate_input(
+                    self.config,
+                    &self.temp_variables,
+                    "",
+                    validate.as_ref(),
+                    validate_command.as_ref(),
+                    options,
+                );
                 self.state_enum = StateEnum::Prompt {
                     command,
                     prefix_len: self.input.len(),
                     output_mode: *output,
+                    options,
+                    validate: validate.as_ref(),
+                    validate_command: validate_command.as_ref(),
+                    validation,
+                    on_enter: *on_enter,
                 };
             }
-            Action::Exec { command } => {
-                if self.cold_run {
-                    dbg!(command, &self.config.variables, &self.temp_variables);
+            ActionKind::Form {
+                fields,
+                command,
+                options,
+            } => {
+                self.state_enum = StateEnum::Form {
+                    prefix_len: self.input.len(),
+                    fields,
+                    index: 0,
+                    filtered: None,
+                    error: None,
+                    command,
+                    options,
+                    saved: Vec::new(),
+                };
+            }
+            ActionKind::Calculator {
+                command,
+                on_enter,
+                options,
+            } => {
+                self.state_enum = StateEnum::Evaluator {
+                    prefix_len: self.input.len(),
+                    evaluator: Evaluator::Calculator,
+                    command: command.as_ref(),
+                    options,
+                    on_enter: *on_enter,
+                };
+            }
+            ActionKind::Convert {
+                units,
+                command,
+                on_enter,
+                options,
+            } => {
+                self.state_enum = StateEnum::Evaluator {
+                    prefix_len: self.input.len(),
+                    evaluator: Evaluator::Convert(units),
+                    command: command.as_ref(),
+                    options,
+                    on_enter: *on_enter,
+                };
+            }
+            ActionKind::Unicode {
+                characters,
+                command,
+            } => {
+                let items = self.generated_list(action, || {
+                    unicode::characters(*characters, command.as_deref())
+                });
+                self.state_enum = StateEnum::List {
+                    prefix_len: self.input.len(),
+                    items,
+                    filtered: None,
+                    on_enter: OnEnter::Run,
+                    limit: file_search::MAX_RESULTS,
+                };
+            }
+            ActionKind::Files {
+                root,
+                hidden,
+                command,
+                options,
+            } => {
+                let root = expand_home(root);
+                self.state_enum = StateEnum::Files {
+                    prefix_len: self.input.len(),
+                    entries: files::read_dir(&root),
+                    root,
+                    dir: String::new(),
+                    filtered: None,
+                    hidden: *hidden,
+                    command: command.as_ref(),
+                    options,
+                };
+            }
+            ActionKind::FileSearch {
+                roots,
+                exclude,
+                hidden,
+                index,
+                command,
+                options,
+            } => {
+                let roots = roots.iter().map(|root| expand_home(root)).collect();
+                let index = index.as_deref().map(expand_home);
+                self.state_enum = match FileSearch::new(roots, exclude, *hidden, index) {
+                    Ok(search) => StateEnum::FileSearch {
+                        prefix_len: self.input.len(),
+                        search: Box::new(search),
+                        command: command.as_ref(),
+                        options,
+                    },
+                    Err(e) => StateEnum::Error(e),
+                };
+            }
+            ActionKind::RecentFiles => {
+                let mut error = None;
+                let items = self.generated_list(action, || {
+                    recent_files::recent_files().unwrap_or_else(|e| {
+                        error = Some(e);
+                        Vec::new()
+                    })
+                });
+                self.state_enum = match error {
+                    Some(e) => StateEnum::Error(e),
+                    None => StateEnum::List {
+                        prefix_len: self.input.len(),
+                        items,
+                        filtered: None,
+                        on_enter: OnEnter::Run,
+                        limit: file_search::MAX_RESULTS,
+                    },
+                };
+            }
+            ActionKind::SshHosts { command, options } => {
+                let items = self.generated_list(action, || ssh_hosts::hosts(command, options));
+                self.state_enum = StateEnum::List {
+                    prefix_len: self.input.len(),
+                    items,
+                    filtered: None,
+                    on_enter: OnEnter::Run,
+                    limit: file_search::MAX_RESULTS,
+                };
+            }
+            ActionKind::Bookmarks { history } => {
+                let items = self.generated_list(action, || bookmarks::bookmarks(*history));
+                self.state_enum = StateEnum::List {
+                    prefix_len: self.input.len(),
+                    items,
+                    filtered: None,
+                    on_enter: OnEnter::Run,
+                    limit: file_search::MAX_RESULTS,
+                };
+            }
+            ActionKind::WebSearch { url, suggest } => {
+                self.state_enum = StateEnum::WebSearch {
+                    prefix_len: self.input.len(),
+                    url,
+                    suggest: suggest
+                        .as_ref()
+                        .map(|command| (command, Suggestions::new())),
+                    selected: None,
+                };
+            }
+            ActionKind::Open { target } => {
+                match State::substitute(self.config, &self.temp_variables, target, Escape::None) {
+                    Ok(target) => self.open(&target),
+                    Err(e) => self.state_enum = StateEnum::Error(e),
+                }
+            }
+            ActionKind::OpenUrl(url) => self.open(url),
+            ActionKind::Copy { text } => {
+                match State::substitute(self.config, &self.temp_variables, text, Escape::None) {
+                    Ok(text) => self.copy(&text),
+                    Err(e) => self.state_enum = StateEnum::Error(e),
+                }
+            }
+            ActionKind::Exec { command, options } => {
+                if self.cold_run {
+                    dbg!(
+                        command,
+                        options,
+                        &self.config.variables,
+                        &self.temp_variables
+                    );
                     self.should_exit = true;
                 } else {
-                    self.exec(command);
+                    match State::create_argv(self.config, &self.temp_variables, command) {
+                        Ok(argv) => self.exec(argv, options),
+                        Err(e) => self.state_enum = StateEnum::Error(e),
+                    }
                 }
             }
-            Action::Submenu { name, variables } => {
+            ActionKind::Shell {
+                command,
+                shell,
+                options,
+            } => {
+                if self.cold_run {
+                    dbg!(
+                        command,
+                        options,
+                        &self.config.variables,
+                        &self.temp_variables
+                    );
+                    self.should_exit = true;
+                } else {
+                    match State::create_shell_argv(
+                        self.config,
+                        &self.temp_variables,
+                        shell.as_ref(),
+                        command,
+                    ) {
+                        Ok(argv) => self.exec(argv, options),
+                        Err(e) => self.state_enum = StateEnum::Error(e),
+                    }
+                }
+            }
+            ActionKind::Submenu {
+                name,
+                menu,
+                variables,
+            } => {
                 let changed_variables: Vec<(&'conf str, Cow<'conf, str>)> = variables
                     .iter()
                     .filter_map(|(k, v)| {
@@ -338,34 +1339,60 @@ impl<'conf> State<'conf> {
                             .map(|o| (k.as_str(), o))
                     })
                     .collect();
-                match self.load_menu(name) {
+                let submenu = match (menu, name) {
+                    (Some(menu), _) => Ok(&**menu),
+                    (None, Some(name)) => self.load_menu(name),
+                    (None, None) => Err(String::from("submenus need a name or a menu.")),
+                };
+                match submenu {
                     Err(e) => {
                         self.state_enum = StateEnum::Error(e);
                     }
                     Ok(submenu) => {
                         self.prompt = &submenu.prompt;
                         self.input = String::new();
-                        self.state_enum = match &submenu.action {
-                            Action::List { items } => StateEnum::List {
-                                prefix_len: 0,
-                                items,
-                                filtered: None,
-                            },
-                            Action::Prompt { command, output } => StateEnum::Prompt {
-                                command,
-                                prefix_len: 0,
-                                output_mode: *output,
-                            },
-                            _ => StateEnum::Error(format!(
-                                "submenus must be a list or a prompt. (encountered in submenu '{name}')"
-                            )),
+                        match &submenu.action {
+                            action if action.takes_input() => self.run_action(action),
+                            _ => {
+                                self.state_enum = StateEnum::Error(format!(
+                                    "submenus must be a list or a prompt. (encountered in submenu '{}')",
+                                    name.as_deref().unwrap_or("<inline>")
+                                ))
+                            }
                         }
                     }
                 }
                 self.temp_variables.extend(changed_variables.into_iter());
             }
-            Action::Exit => self.should_exit = true,
-            Action::LaunchApp(path_buf) => {
+            ActionKind::Exit => self.should_exit = true,
+            ActionKind::SetVariable {
+                name,
+                value,
+                command,
+                options,
+            } => match self.set_variable(name, value.as_ref(), command.as_ref(), options) {
+                Ok(()) => {
+                    self.input.clear();
+                    self.state_enum = StateEnum::MainMenu {
+                        items: &self.config.command,
+                        filtered: None,
+                    };
+                }
+                Err(e) => self.state_enum = StateEnum::Error(e),
+            },
+            ActionKind::Sequence {
+                steps,
+                stop_on_failure,
+                capture,
+            } => {
+                if self.cold_run {
+                    dbg!(steps, &self.config.variables, &self.temp_variables);
+                    self.should_exit = true;
+                } else {
+                    self.run_sequence(steps, *stop_on_failure, capture.as_ref());
+                }
+            }
+            ActionKind::LaunchApp(path_buf) => {
                 #[cfg(feature = "launch")]
                 {
                     use gio::{AppLaunchContext, DesktopAppInfo, prelude::AppInfoExt};
@@ -390,83 +1417,432 @@ impl<'conf> State<'conf> {
         }
     }
 
-    fn create_cmd_iter(
-        config: &Config,
+    fn substitute<'a>(
+        config: &'a Config,
+        temp_vars: &HashMap<&str, Cow<'a, str>>,
+        s: &'a str,
+        escape: Escape,
+    ) -> Result<Cow<'a, str>, String> {
+        template::render(
+            s,
+            |name| {
+                temp_vars
+                    .get(name)
+                    .map(|v| v.as_ref())
+                    .or_else(|| config.variables.get(name).map(String::as_str))
+            },
+            escape,
+            config.general.strict_variables,
+        )
+    }
+
+    fn create_argv(
+        config: &'conf Config,
         temp_vars: &HashMap<&str, Cow<'conf, str>>,
-        cmd: &'conf Vec<String>,
-    ) -> impl Iterator<Item = Cow<'conf, OsStr>> {
-        cmd.iter().map(move |s| {
-            let str = regex_replace_all!(r"\{\{([\w]+)\}\}", s, |_, name| {
-                if let Some(v) = temp_vars.get(name) {
-                    v
-                } else {
-                    config.variables.get(name).map(String::as_str).unwrap_or("")
-                }
-            });
+        cmd: &'conf [String],
+    ) -> Result<Vec<Cow<'conf, OsStr>>, String> {
+        cmd.iter()
+            .map(|s| {
+                State::substitute(config, temp_vars, s, Escape::None).map(|s| match s {
+                    Cow::Borrowed(s) => Cow::Borrowed(OsStr::new(s)),
+                    Cow::Owned(s) => Cow::Owned(OsString::from(s)),
+                })
+            })
+            .collect()
+    }
+
+    fn create_shell_argv(
+        config: &'conf Config,
+        temp_vars: &HashMap<&str, Cow<'conf, str>>,
+        shell: Option<&'conf Vec<String>>,
+        script: &'conf str,
+    ) -> Result<Vec<Cow<'conf, OsStr>>, String> {
+        let shell = shell.or(config.general.shell.as_ref());
+        let script = match State::substitute(config, temp_vars, script, Escape::Shell)? {
+            Cow::Borrowed(s) => Cow::Borrowed(OsStr::new(s)),
+            Cow::Owned(s) => Cow::Owned(OsString::from(s)),
+        };
+        Ok(match shell {
+            Some(shell) => shell.iter().map(|s| Cow::Borrowed(OsStr::new(s))).collect(),
+            None => vec![
+                Cow::Borrowed(OsStr::new("sh")),
+                Cow::Borrowed(OsStr::new("-c")),
+            ],
+        }
+        .into_iter()
+        .chain([script])
+        .collect())
+    }
 
-            match str {
-                Cow::Borrowed(s) => Cow::Borrowed(OsStr::new(s)),
-                Cow::Owned(s) => Cow::Owned(OsString::from(s)),
+    fn create_command_argv(
+        config: &'conf Config,
+        temp_vars: &HashMap<&str, Cow<'conf, str>>,
+        command: &'conf CommandLine,
+    ) -> Result<Vec<Cow<'conf, OsStr>>, String> {
+        match command {
+            CommandLine::Argv(argv) => State::create_argv(config, temp_vars, argv),
+            CommandLine::Shell(script) => State::create_shell_argv(config, temp_vars, None, script),
+        }
+    }
+
+    fn build_command(
+        config: &'conf Config,
+        temp_vars: &HashMap<&str, Cow<'conf, str>>,
+        argv: impl IntoIterator<Item = Cow<'conf, OsStr>>,
+        options: &'conf ExecOptions,
+    ) -> Result<Command, String> {
+        let mut argv = argv.into_iter();
+        let mut command = Command::new(argv.next().ok_or("empty command.")?);
+        command.args(argv).envs(&mut vars_os()).stdin(Stdio::null());
+        for (k, v) in &options.env {
+            command.env(
+                k,
+                State::substitute(config, temp_vars, v, Escape::None)?.as_ref(),
+            );
+        }
+        if let Some(cwd) = &options.cwd {
+            command.current_dir(expand_home(&State::substitute(
+                config,
+                temp_vars,
+                cwd,
+                Escape::None,
+            )?));
+        }
+        if options.detach {
+            command.process_group(0);
+        }
+        Ok(command)
+    }
+
+    fn wait_timeout(child: &mut Child, timeout: Duration) -> Result<ExitStatus, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return Ok(status),
+                Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(5)),
+                Ok(None) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(format!(
+                        "command timed out after {}ms.",
+                        timeout.as_millis()
+                    ));
+                }
+                Err(e) => return Err(format!("{e}")),
             }
+        }
+    }
+
+    /// runs a command to completion and captures its stdout, killing it after `timeout`.
+    fn capture(
+        config: &'conf Config,
+        temp_vars: &HashMap<&str, Cow<'conf, str>>,
+        argv: impl IntoIterator<Item = Cow<'conf, OsStr>>,
+        options: &'conf ExecOptions,
+        timeout: Option<Duration>,
+    ) -> Result<Output, String> {
+        let mut child = State::build_command(config, temp_vars, argv, options)?
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("{e}"))?;
+        let Some(timeout) = timeout else {
+            return child.wait_with_output().map_err(|e| format!("{e}"));
+        };
+        // stdout is read while waiting, otherwise a command that fills the pipe would block
+        // until it's killed.
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let reader = thread::spawn(move || {
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output).map(|_| output)
+        });
+        let status = State::wait_timeout(&mut child, timeout)?;
+        let stdout = reader
+            .join()
+            .expect("reader doesn't panic")
+            .map_err(|e| format!("{e}"))?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr: Vec::new(),
         })
     }
 
-    pub(crate) fn exec(&mut self, cmd: &Vec<String>) {
-        let mut cmd_iter = State::create_cmd_iter(self.config, &self.temp_variables, cmd);
-
-        if let Some(program) = cmd_iter.next() {
-            match Command::new(program)
-                .args(cmd_iter)
-                .envs(&mut vars_os())
-                .stdin(Stdio::null())
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .spawn()
-            {
-                Ok(_) => (),
-                Err(e) => self.state_enum = StateEnum::Error(format!("{e}")),
-            };
+    /// returns a message describing why `input` is invalid, or `None` if it's valid.
+    fn validate_input(
+        config: &'conf Config,
+        temp_vars: &HashMap<&str, Cow<'conf, str>>,
+        input: &str,
+        validate: Option<&Regex>,
+        validate_command: Option<&'conf Vec<String>>,
+        options: &'conf ExecOptions,
+    ) -> Option<String> {
+        if let Some(re) = validate
+            && !re.is_match(input)
+        {
+            return Some(format!("input must match {}.", re.as_str()));
         }
 
-        self.should_exit = true;
+        let command = validate_command?;
+        let mut tvars = temp_vars.clone();
+        tvars.insert("INPUT", Cow::Borrowed(input));
+        match State::create_argv(config, &tvars, command).and_then(|argv| {
+            let timeout = options.timeout_ms.map(Duration::from_millis);
+            State::capture(
+                config,
+                &tvars,
+                argv,
+                options,
+                timeout.or(Some(VALIDATE_TIMEOUT)),
+            )
+        }) {
+            Ok(output) if output.status.success() => None,
+            Ok(output) => {
+                let message = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                Some(if message.is_empty() {
+                    String::from("invalid input.")
+                } else {
+                    message
+                })
+            }
+            Err(e) => Some(e),
+        }
     }
 
-    fn load_menu(&mut self, name: &'conf String) -> Result<&'static Submenu, String> {
-        match self.loaded_menus.get(name) {
-            None => {
-                let Ok(file) = fs::read_to_string(format!(
-                    "{}/superspace/{name}.toml",
-                    dirs::config_dir().expect("no config dir").to_string_lossy()
-                )) else {
-                    return Err(format!("file not found: ~/.config/superspace/{name}.toml"));
-                };
-                match toml::from_str::<Submenu>(&file) {
-                    // SAFETY: this is an on-purpose memory leak. for my purposes, there will only
-                    // ever be 1 State, which will never be de-allocated until the end of the
-                    // program, so leaking this memory is okay.
-                    Ok(m) => Ok(Box::leak(Box::new(m))),
-                    Err(e) => {
-                        return Err(e.message().to_string());
+    fn spawn(
+        &self,
+        argv: Vec<Cow<'conf, OsStr>>,
+        options: &'conf ExecOptions,
+    ) -> Result<(), String> {
+        State::build_command(self.config, &self.temp_variables, argv, options)
+            .and_then(|mut command| {
+                command
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .map_err(|e| format!("{e}"))
+            })
+            .and_then(|mut child| match options.timeout_ms {
+                Some(ms) if !options.detach => {
+                    match State::wait_timeout(&mut child, Duration::from_millis(ms))? {
+                        status if status.success() => Ok(()),
+                        status => Err(format!("command failed ({status}).")),
+                    }
+                }
+                _ => Ok(()),
+            })
+    }
+
+    pub(crate) fn exec(&mut self, argv: Vec<Cow<'conf, OsStr>>, options: &'conf ExecOptions) {
+        // errors keep the session open, so they're shown.
+        match self.spawn(argv, options) {
+            Ok(()) => self.should_exit = true,
+            Err(e) => self.state_enum = StateEnum::Error(e),
+        }
+    }
+
+    /// runs a command as part of a sequence. steps before the last are waited for unless they're
+    /// detached, so their output can be captured. the last one runs like a normal exec, so a gui
+    /// app doesn't block the launcher.
+    fn exec_step(
+        &self,
+        argv: Vec<Cow<'conf, OsStr>>,
+        options: &'conf ExecOptions,
+        last: bool,
+    ) -> Result<Option<String>, String> {
+        if options.detach || last {
+            return self.spawn(argv, options).map(|()| None);
+        }
+        self.capture_trimmed(argv, options).map(Some)
+    }
+
+    fn capture_trimmed(
+        &self,
+        argv: Vec<Cow<'conf, OsStr>>,
+        options: &'conf ExecOptions,
+    ) -> Result<String, String> {
+        let timeout = options.timeout_ms.map(Duration::from_millis);
+        let output = State::capture(self.config, &self.temp_variables, argv, options, timeout)?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        } else {
+            Err(format!("command failed ({}).", output.status))
+        }
+    }
+
+    fn set_variable(
+        &mut self,
+        name: &'conf str,
+        value: Option<&'conf String>,
+        command: Option<&'conf Vec<String>>,
+        options: &'conf ExecOptions,
+    ) -> Result<(), String> {
+        let value = match (value, command) {
+            (Some(value), _) => {
+                State::substitute(self.config, &self.temp_variables, value, Escape::None)?
+                    .into_owned()
+            }
+            (None, Some(command)) => {
+                let argv = State::create_argv(self.config, &self.temp_variables, command)?;
+                self.capture_trimmed(argv, options)?
+            }
+            (None, None) => {
+                return Err(format!(
+                    "set_variable '{name}' needs either a value or a command."
+                ));
+            }
+        };
+        self.temp_variables.insert(name, Cow::Owned(value));
+        Ok(())
+    }
+
+    fn run_sequence(
+        &mut self,
+        steps: &'conf [Action],
+        stop_on_failure: bool,
+        capture: Option<&'conf String>,
+    ) {
+        if steps.iter().rev().skip(1).any(Action::waits_for_input) {
+            self.state_enum = StateEnum::Error(String::from(
+                "only the last step of a sequence can wait for input.",
+            ));
+            return;
+        }
+
+        for (i, step) in steps.iter().enumerate() {
+            let last = i + 1 == steps.len();
+            let result = match &step.kind {
+                ActionKind::Exec { command, options } if step.confirm.is_none() => {
+                    State::create_argv(self.config, &self.temp_variables, command)
+                        .and_then(|argv| self.exec_step(argv, options, last))
+                }
+                ActionKind::Shell {
+                    command,
+                    shell,
+                    options,
+                } if step.confirm.is_none() => State::create_shell_argv(
+                    self.config,
+                    &self.temp_variables,
+                    shell.as_ref(),
+                    command,
+                )
+                .and_then(|argv| self.exec_step(argv, options, last)),
+                ActionKind::SetVariable {
+                    name,
+                    value,
+                    command,
+                    options,
+                } if step.confirm.is_none() => self
+                    .set_variable(name, value.as_ref(), command.as_ref(), options)
+                    .map(|()| None),
+                _ => {
+                    self.run_cmd(step);
+                    match &self.state_enum {
+                        StateEnum::Error(e) => Err(e.clone()),
+                        _ => Ok(None),
                     }
                 }
+            };
+
+            match result {
+                Ok(Some(output)) => {
+                    if let Some(name) = capture {
+                        self.temp_variables.insert(name, Cow::Owned(output));
+                    }
+                }
+                Ok(None) => (),
+                Err(e) if stop_on_failure => {
+                    self.should_exit = false;
+                    self.state_enum = StateEnum::Error(e);
+                    return;
+                }
+                Err(_) => (),
             }
-            Some(a) => Ok(*a),
         }
+
+        if !steps.last().is_some_and(Action::waits_for_input) {
+            self.should_exit = true;
+        }
+    }
+
+    /// returns the items of a built-in list action, generating them the first time.
+    fn generated_list(
+        &mut self,
+        action: &'conf ActionKind,
+        generate: impl FnOnce() -> Vec<ListItem>,
+    ) -> &'conf Vec<ListItem> {
+        if let Some((_, items)) = self.generated.iter().find(|(a, _)| ptr::eq(*a, action)) {
+            return items;
+        }
+        let items: &'conf Vec<ListItem> = self.menus.lists.alloc(generate());
+        self.generated.push((action, items));
+        items
+    }
+
+    /// the submenu files read so far in this session.
+    pub(crate) fn menu_files(&self) -> &[PathBuf] {
+        &self.menu_files
+    }
+
+    /// looks up a submenu, reading and caching it the first time a file-based menu is used.
+    fn load_menu(&mut self, name: &'conf String) -> Result<&'conf Submenu, String> {
+        if let Some(submenu) = self.config.submenus.get(name) {
+            return Ok(submenu);
+        }
+        if let Some(submenu) = self.loaded_menus.get(name.as_str()) {
+            return Ok(submenu);
+        }
+
+        let (path, file) = self.config.find_menu(name)?;
+        if !self.menu_files.contains(&path) {
+            self.menu_files.push(path.clone());
+        }
+        let submenu: &'conf Submenu = self
+            .menus
+            .submenus
+            .alloc(toml::from_str(&file).map_err(|e| describe_error(&path, &file, &e))?);
+        self.loaded_menus.insert(name, submenu);
+        Ok(submenu)
     }
 }
 
+/// escapes `input` for use inside a json string.
+pub(crate) fn sanitize(input: &str) -> Cow<'_, str> {
+    regex_replace_all!(r#"["\\\n\r\t]"#, input, |c: &str| match c {
+        "\n" => String::from("\\n"),
+        "\r" => String::from("\\r"),
+        "\t" => String::from("\\t"),
+        c => String::from('\\') + c,
+    })
+}
+
 impl Display for State<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fn sanitize(input: &str) -> Cow<'_, str> {
-            regex_replace_all!(r#"["\\]"#, input, |c| String::from('\\') + c)
+        /// the contents of a json array of strings.
+        fn string_list<'a>(mut iter: impl Iterator<Item = &'a str>) -> String {
+            if let Some(first) = iter.next() {
+                let mut string = String::from('"');
+                string.push_str(&sanitize(first));
+                for item in iter {
+                    string.push_str("\", \"");
+                    string.push_str(&sanitize(item));
+                }
+                string.push('"');
+                string
+            } else {
+                String::new()
+            }
         }
 
         let input = sanitize(&self.input);
-        let prompt = self
+        let mut prompt = self
             .prompt
             .as_ref()
-            .map(|p| format!(r#", "prompt": "{}""#, sanitize(p)));
-        let prompt = prompt.as_ref().map(|s| s.as_str()).unwrap_or("");
+            .map(|p| format!(r#", "prompt": "{}""#, sanitize(p)))
+            .unwrap_or_default();
+        if let Some(error) = &self.config_error {
+            prompt.push_str(&format!(r#","config_error":"{}""#, sanitize(error)));
+        }
 
         match &self.state_enum {
             StateEnum::MainMenu { items, filtered } => {
@@ -516,80 +1892,237 @@ impl Display for State<'_> {
                 prefix_len,
                 command,
                 output_mode,
+                options,
+                validate,
+                validate_command,
+                validation,
+                ..
             } => {
                 let output =
                     if *output_mode == OutputMode::Continuous && self.input.len() > *prefix_len {
                         let mut tvars = self.temp_variables.clone();
                         tvars.insert("INPUT", Cow::Borrowed(&self.input[*prefix_len..]));
-                        let mut iter = State::create_cmd_iter(self.config, &tvars, command);
-
-                        if let Some(program) = iter.next() {
-                            Command::new(program)
-                                .args(iter)
-                                .stdin(Stdio::null())
-                                .output()
-                                .ok()
-                                .map(|output| {
-                                    let output = String::from_utf8_lossy(&output.stdout);
-                                    format!(r#","output":"{}""#, output.trim())
-                                })
-                        } else {
-                            None
+
+                        match State::create_command_argv(self.config, &tvars, command).and_then(
+                            |argv| {
+                                let timeout = options.timeout_ms.map(Duration::from_millis);
+                                State::capture(self.config, &tvars, argv, options, timeout)
+                            },
+                        ) {
+                            Ok(output) => {
+                                let output = String::from_utf8_lossy(&output.stdout);
+                                format!(r#","output":"{}""#, sanitize(output.trim()))
+                            }
+                            Err(e) => format!(r#","error":"{}""#, sanitize(&e)),
                         }
                     } else {
-                        None
+                        String::new()
                     };
-                let output = output.as_ref().map(String::as_str).unwrap_or("");
+                let valid = match validation {
+                    _ if validate.is_none() && validate_command.is_none() => String::new(),
+                    Some(message) => format!(
+                        r#","valid":false,"validation_message":"{}""#,
+                        sanitize(message)
+                    ),
+                    None => String::from(r#","valid":true"#),
+                };
 
                 write!(
                     f,
-                    r#"{{"type":"prompt","input":"{input}","prefix":"{prefix}"{output}{prompt}}}"#,
+                    r#"{{"type":"prompt","input":"{input}","prefix":"{prefix}"{output}{valid}{prompt}}}"#,
                     prefix = &input[..*prefix_len]
                 )
             }
             StateEnum::List {
-                prefix_len: _,
                 items,
                 filtered,
+                limit,
+                ..
             } => {
-                fn stringify<'a>(mut iter: impl Iterator<Item = &'a String>) -> String {
-                    if let Some(first) = iter.next() {
-                        let mut string = String::from('"');
-                        string.push_str(first);
-                        for item in iter {
-                            string.push_str("\", \"");
-                            string.push_str(item);
-                        }
-                        string.push('"');
-                        string
-                    } else {
-                        String::new()
-                    }
+                let shown: Vec<&ListItem> = match filtered {
+                    Some(f) => f
+                        .iter()
+                        .take(self.max_items.unwrap_or(*limit))
+                        .map(|(item, _)| *item)
+                        .collect(),
+                    None => items
+                        .iter()
+                        .take(self.max_items.unwrap_or(*limit))
+                        .collect(),
+                };
+                let items = string_list(shown.iter().map(|item| item.name.as_str()));
+                // descriptions are only sent when there are any, and line up with the items.
+                let descriptions = if shown.iter().any(|item| item.description.is_some()) {
+                    let descriptions = shown
+                        .iter()
+                        .map(|item| item.description.as_deref().unwrap_or(""));
+                    format!(r#","descriptions":[{}]"#, string_list(descriptions))
+                } else {
+                    String::new()
+                };
+
+                write!(
+                    f,
+                    r#"{{"type":"list","input":"{input}","items":[{items}]{descriptions}{prompt}}}"#,
+                )
+            }
+            StateEnum::Form {
+                prefix_len,
+                fields,
+                index,
+                filtered,
+                error,
+                ..
+            } => {
+                let field = &fields[*index];
+                let mut extra = String::new();
+                if let Some(field_prompt) = &field.prompt {
+                    extra.push_str(&format!(r#","field_prompt":"{}""#, sanitize(field_prompt)));
                 }
-                let items = filtered.as_ref().map_or_else(
-                    || {
-                        stringify(
-                            items
+                if let Some(default) = &field.default {
+                    extra.push_str(&format!(r#","default":"{}""#, sanitize(default)));
+                }
+                if let Some(choices) = &field.choices {
+                    let items: Vec<_> = match filtered {
+                        Some(f) => f.iter().map(|(c, _)| *c).collect(),
+                        None => choices.iter().collect(),
+                    };
+                    let items: Vec<_> = items
+                        .into_iter()
+                        .take(self.max_items.unwrap_or(usize::MAX))
+                        .map(|c| format!(r#""{}""#, sanitize(c)))
+                        .collect();
+                    extra.push_str(&format!(r#","items":[{}]"#, items.join(",")));
+                }
+                if let Some(error) = error {
+                    extra.push_str(&format!(r#","error":"{}""#, sanitize(error)));
+                }
+
+                write!(
+                    f,
+                    r#"{{"type":"form","input":"{input}","prefix":"{prefix}","field":"{field}","index":{index},"count":{count}{extra}{prompt}}}"#,
+                    prefix = &input[..*prefix_len],
+                    field = sanitize(&field.name),
+                    count = fields.len(),
+                )
+            }
+            StateEnum::Confirm { message, .. } => write!(
+                f,
+                r#"{{"type":"confirm","input":"{input}","message":"{}"{prompt}}}"#,
+                sanitize(message)
+            ),
+            StateEnum::Evaluator {
+                prefix_len,
+                evaluator,
+                ..
+            } => {
+                let expression = &self.input[*prefix_len..];
+                let result = match evaluator.evaluate(expression) {
+                    _ if expression.trim().is_empty() => String::new(),
+                    Ok(result) => {
+                        format!(r#","output":"{}","valid":true"#, sanitize(&result))
+                    }
+                    Err(e) => format!(r#","valid":false,"validation_message":"{}""#, sanitize(&e)),
+                };
+                write!(
+                    f,
+                    r#"{{"type":"prompt","input":"{input}","prefix":"{prefix}"{result}{prompt}}}"#,
+                    prefix = &input[..*prefix_len]
+                )
+            }
+            StateEnum::Files {
+                prefix_len,
+                root,
+                dir,
+                entries,
+                filtered,
+                hidden,
+                ..
+            } => {
+                let query = self.input[*prefix_len..]
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default();
+                let show_hidden = *hidden || query.starts_with('.');
+                let names: Vec<String> = match (filtered, entries) {
+                    (Some(filtered), _) => filtered
+                        .iter()
+                        .take(self.max_items.unwrap_or(usize::MAX))
+                        .map(FileEntry::display_name)
+                        .collect(),
+                    (None, Ok(entries)) => entries
+                        .iter()
+                        .filter(|e| show_hidden || !e.is_hidden())
+                        .take(self.max_items.unwrap_or(usize::MAX))
+                        .map(FileEntry::display_name)
+                        .collect(),
+                    (None, Err(_)) => Vec::new(),
+                };
+                let items = string_list(names.iter().map(String::as_str));
+                let error = match entries {
+                    Err(e) => format!(r#","error":"{}""#, sanitize(e)),
+                    Ok(_) => String::new(),
+                };
+                let directory = files::resolve(root, dir);
+
+                write!(
+                    f,
+                    r#"{{"type":"list","input":"{input}","items":[{items}],"directory":"{}"{error}{prompt}}}"#,
+                    sanitize(&directory.to_string_lossy())
+                )
+            }
+            StateEnum::FileSearch { search, .. } => {
+                let paths: Vec<String> = search
+                    .results(self.max_items.unwrap_or(file_search::MAX_RESULTS))
+                    .map(file_search::display_path)
+                    .collect();
+                let items = string_list(paths.iter().map(String::as_str));
+                let searching = if search.is_walking() {
+                    r#","searching":true"#
+                } else {
+                    ""
+                };
+
+                write!(
+                    f,
+                    r#"{{"type":"list","input":"{input}","items":[{items}]{searching}{prompt}}}"#,
+                )
+            }
+            StateEnum::WebSearch {
+                prefix_len,
+                suggest,
+                selected,
+                ..
+            } => {
+                let suggestions = match suggest {
+                    Some((_, suggestions)) => format!(
+                        r#","suggestions":[{}]"#,
+                        string_list(
+                            suggestions
+                                .suggestions()
                                 .iter()
                                 .take(self.max_items.unwrap_or(usize::MAX))
-                                .map(|item| &item.name),
-                        )
-                    },
-                    |f| {
-                        stringify(
-                            f.iter()
-                                .take(self.max_items.unwrap_or(usize::MAX))
-                                .map(|(item, _)| &item.name),
+                                .map(String::as_str)
                         )
-                    },
-                );
-
+                    ),
+                    None => String::new(),
+                };
+                let selected = match selected {
+                    Some(i) => format!(r#","selected":{i}"#),
+                    None => String::new(),
+                };
                 write!(
                     f,
-                    r#"{{"type":"list","input":"{input}","items":[{items}]{prompt}}}"#,
+                    r#"{{"type":"prompt","input":"{input}","prefix":"{prefix}"{suggestions}{selected}{prompt}}}"#,
+                    prefix = &input[..*prefix_len]
                 )
             }
-            StateEnum::Error(msg) => write!(f, r#"{{"type":"error","message":"{msg}"}}"#,),
+            StateEnum::Output(text) => {
+                write!(f, r#"{{"type":"output","text":"{}"}}"#, sanitize(text))
+            }
+            StateEnum::Error(msg) => {
+                write!(f, r#"{{"type":"error","message":"{}"}}"#, sanitize(msg))
+            }
         }
     }
 }
@@ -0,0 +1,262 @@
+use std::{borrow::Cow, env};
+
+use lazy_regex::regex;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// how values are escaped when no filter has escaped them already.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Escape {
+    None,
+    /// shell-quoted, unless the `raw` or `shell` filter is used.
+    Shell,
+    /// url-encoded, unless the `raw` or `urlencode` filter is used.
+    Url,
+}
+
+/// replaces every `{{name|filter|filter:arg}}` in `template`. names starting with `env.` are
+/// read from the environment, everything else goes through `lookup`.
+///
+/// values are escaped as `escape` says. if `strict` is set, undefined variables without a
+/// default are an error instead of "".
+pub(crate) fn render<'a, 'v>(
+    template: &'a str,
+    lookup: impl Fn(&str) -> Option<&'v str>,
+    escape: Escape,
+    strict: bool,
+) -> Result<Cow<'a, str>, String> {
+    let re = regex!(r"\{\{\s*([\w.]+)\s*((?:\|[^|}]*)*)\}\}");
+    let mut output = String::new();
+    let mut last = 0;
+
+    for captures in re.captures_iter(template) {
+        let whole = captures.get(0).expect("group 0 always matches");
+        output.push_str(&template[last..whole.start()]);
+        last = whole.end();
+
+        let name = &captures[1];
+        let mut value = match name.strip_prefix("env.") {
+            Some(var) => env::var(var).ok().map(Cow::Owned),
+            None => lookup(name).map(Cow::Borrowed),
+        };
+        let mut escaped = escape == Escape::None;
+
+        for filter in captures[2].split('|').skip(1) {
+            let (filter, arg) = filter.split_once(':').unwrap_or((filter, ""));
+            match filter.trim() {
+                "default" => {
+                    if value.as_ref().is_none_or(|v| v.is_empty()) {
+                        value = Some(Cow::Borrowed(arg));
+                    }
+                }
+                "raw" => escaped = true,
+                "shell" => {
+                    value = value.map(|v| Cow::Owned(shell_quote(&v).into_owned()));
+                    escaped = true;
+                }
+                "urlencode" => {
+                    value = value
+                        .map(|v| Cow::Owned(utf8_percent_encode(&v, URL_ENCODE_SET).to_string()));
+                    escaped |= escape == Escape::Url;
+                }
+                "upper" => value = value.map(|v| Cow::Owned(v.to_uppercase())),
+                "lower" => value = value.map(|v| Cow::Owned(v.to_lowercase())),
+                "trim" => value = value.map(|v| Cow::Owned(v.trim().to_string())),
+                "json" => {
+                    value = value.map(|v| {
+                        Cow::Owned(serde_json::to_string(&v).expect("strings are valid json"))
+                    })
+                }
+                other => return Err(format!("unknown filter '{other}' in '{template}'.")),
+            }
+        }
+
+        let value = match value {
+            Some(v) => v,
+            None if strict => return Err(format!("undefined variable '{name}'.")),
+            None => Cow::Borrowed(""),
+        };
+        match escape {
+            Escape::Shell if !escaped => output.push_str(&shell_quote(&value)),
+            Escape::Url if !escaped => output.extend(utf8_percent_encode(&value, URL_ENCODE_SET)),
+            _ => output.push_str(&value),
+        }
+    }
+
+    if last == 0 {
+        Ok(Cow::Borrowed(template))
+    } else {
+        output.push_str(&template[last..]);
+        Ok(Cow::Owned(output))
+    }
+}
+
+pub(crate) fn shell_quote(s: &str) -> Cow<'_, str> {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c))
+    {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(format!("'{}'", s.replace('\'', r"'\''")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("it's me"),
+            "query" => Some("a&b c"),
+            "empty" => Some(""),
+            "padded" => Some("  Mixed Case  "),
+            _ => None,
+        }
+    }
+
+    fn render_with(template: &str, escape: Escape) -> Result<String, String> {
+        render(template, lookup, escape, false).map(Cow::into_owned)
+    }
+
+    #[test]
+    fn plain() {
+        assert_eq!(
+            render_with("hello {{name}}!", Escape::None).unwrap(),
+            "hello it's me!"
+        );
+        assert_eq!(render_with("{{ name }}", Escape::None).unwrap(), "it's me");
+        assert!(matches!(
+            render("no variables", lookup, Escape::None, false),
+            Ok(Cow::Borrowed(_))
+        ));
+        assert_eq!(render_with("{{missing}}", Escape::None).unwrap(), "");
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(
+            render_with("{{padded|trim}}", Escape::None).unwrap(),
+            "Mixed Case"
+        );
+        assert_eq!(
+            render_with("{{padded|trim|upper}}", Escape::None).unwrap(),
+            "MIXED CASE"
+        );
+        assert_eq!(
+            render_with("{{padded|trim|lower}}", Escape::None).unwrap(),
+            "mixed case"
+        );
+        assert_eq!(
+            render_with("{{query|urlencode}}", Escape::None).unwrap(),
+            "a%26b%20c"
+        );
+        assert_eq!(
+            render_with("{{name|json}}", Escape::None).unwrap(),
+            r#""it's me""#
+        );
+        assert_eq!(
+            render_with("{{name|shell}}", Escape::None).unwrap(),
+            r"'it'\''s me'"
+        );
+        assert_eq!(
+            render_with("{{name|reverse}}", Escape::None),
+            Err(String::from(
+                "unknown filter 'reverse' in '{{name|reverse}}'."
+            ))
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(
+            render_with("{{missing|default:none}}", Escape::None).unwrap(),
+            "none"
+        );
+        assert_eq!(
+            render_with("{{empty|default:none}}", Escape::None).unwrap(),
+            "none"
+        );
+        assert_eq!(
+            render_with("{{name|default:none}}", Escape::None).unwrap(),
+            "it's me"
+        );
+        assert_eq!(
+            render_with("{{missing|default:a b|upper}}", Escape::None).unwrap(),
+            "A B"
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            render_with("echo {{name}}", Escape::Shell).unwrap(),
+            r"echo 'it'\''s me'"
+        );
+        assert_eq!(
+            render_with("echo {{name|raw}}", Escape::Shell).unwrap(),
+            "echo it's me"
+        );
+        assert_eq!(
+            render_with("echo {{name|shell}}", Escape::Shell).unwrap(),
+            r"echo 'it'\''s me'"
+        );
+        assert_eq!(
+            render_with("echo {{empty}}", Escape::Shell).unwrap(),
+            "echo ''"
+        );
+        assert_eq!(
+            render_with("?q={{query}}", Escape::Url).unwrap(),
+            "?q=a%26b%20c"
+        );
+        // urlencode doesn't encode twice, and raw isn't encoded at all.
+        assert_eq!(
+            render_with("?q={{query|urlencode}}", Escape::Url).unwrap(),
+            "?q=a%26b%20c"
+        );
+        assert_eq!(
+            render_with("?q={{query|raw}}", Escape::Url).unwrap(),
+            "?q=a&b c"
+        );
+    }
+
+    #[test]
+    fn environment() {
+        let home = env::var("HOME").unwrap_or_default();
+        assert_eq!(render_with("{{env.HOME}}", Escape::None).unwrap(), home);
+        assert_eq!(
+            render_with("{{env.SUPERSPACE_UNSET_VARIABLE|default:x}}", Escape::None).unwrap(),
+            "x"
+        );
+    }
+
+    #[test]
+    fn strict() {
+        assert_eq!(
+            render("{{missing}}", lookup, Escape::None, true),
+            Err(String::from("undefined variable 'missing'."))
+        );
+        assert_eq!(
+            render(
+                "{{env.SUPERSPACE_UNSET_VARIABLE}}",
+                lookup,
+                Escape::None,
+                true
+            ),
+            Err(String::from(
+                "undefined variable 'env.SUPERSPACE_UNSET_VARIABLE'."
+            ))
+        );
+        assert_eq!(
+            render("{{missing|default:}}", lookup, Escape::None, true).unwrap(),
+            ""
+        );
+        assert_eq!(render("{{empty}}", lookup, Escape::None, true).unwrap(), "");
+    }
+}
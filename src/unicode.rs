@@ -0,0 +1,93 @@
+use std::{collections::HashSet, sync::OnceLock, thread};
+
+use crate::config::{ActionKind, ExecOptions, ListItem};
+
+struct Character {
+    glyph: String,
+    name: String,
+    description: String,
+    search: String,
+}
+
+/// every character, with emoji first. the number of emoji is kept to leave out the rest.
+static CHARACTERS: OnceLock<(Vec<Character>, usize)> = OnceLock::new();
+
+/// starts building the character table in the background, since it takes a moment.
+pub fn preload() {
+    thread::spawn(table);
+}
+
+/// builds the items for the unicode picker. each item copies its character, or runs `command`
+/// with `{{CHAR}}` set to it.
+pub fn characters(include_characters: bool, command: Option<&[String]>) -> Vec<ListItem> {
+    let (characters, emoji) = table();
+    let characters = if include_characters {
+        &characters[..]
+    } else {
+        &characters[..*emoji]
+    };
+
+    characters
+        .iter()
+        .map(|c| ListItem {
+            action: match command {
+                Some(command) => ActionKind::Exec {
+                    command: command.to_vec(),
+                    options: ExecOptions::default(),
+                },
+                None => ActionKind::Copy {
+                    text: String::from("{{CHAR}}"),
+                },
+            }
+            .into(),
+            name: format!("{} {}", c.glyph, c.name),
+            description: Some(c.description.clone()),
+            search: Some(c.search.clone()),
+            variables: vec![("CHAR", c.glyph.clone())],
+        })
+        .collect()
+}
+
+fn table() -> &'static (Vec<Character>, usize) {
+    CHARACTERS.get_or_init(|| {
+        let mut characters = Vec::new();
+        let mut emoji = HashSet::new();
+        for e in emojis::iter() {
+            let shortcodes: Vec<_> = e.shortcodes().map(|s| format!(":{s}:")).collect();
+            characters.push(Character {
+                glyph: e.as_str().to_string(),
+                name: e.name().to_string(),
+                description: shortcodes.join(" "),
+                search: format!("{} {}", e.name(), shortcodes.join(" ")),
+            });
+            emoji.insert(e.as_str());
+        }
+        let emoji_count = characters.len();
+
+        // the supplementary planes past 1 are almost all ideographs.
+        for c in ('\u{20}'..='\u{1ffff}').filter(|c| !c.is_control()) {
+            let mut buf = [0; 4];
+            let glyph = &*c.encode_utf8(&mut buf);
+            if emoji.contains(glyph) {
+                continue;
+            }
+            let Some(name) = unicode_names2::name(c) else {
+                continue;
+            };
+            let name = name.to_string();
+            // these are named by code point, so searching them by name is pointless.
+            if name.contains("IDEOGRAPH") || name.starts_with("HANGUL SYLLABLE") {
+                continue;
+            }
+            let name = name.to_lowercase();
+            characters.push(Character {
+                glyph: glyph.to_string(),
+                name: name.clone(),
+                description: format!("U+{:04X}", c as u32),
+                search: name,
+            });
+        }
+
+        (characters, emoji_count)
+    })
+}
@@ -0,0 +1,38 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// polls files for changes by comparing their modification times.
+#[derive(Default)]
+pub struct Watcher {
+    modified: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl Watcher {
+    /// returns true if any of `paths` changed since the last call. paths that haven't been seen
+    /// before are only recorded.
+    pub fn changed<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>) -> bool {
+        let mut changed = false;
+        for path in paths {
+            let modified = modified(path);
+            match self.modified.get_mut(path) {
+                Some(old) if *old != modified => {
+                    *old = modified;
+                    changed = true;
+                }
+                Some(_) => (),
+                None => {
+                    self.modified.insert(path.clone(), modified);
+                }
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,109 @@
+use std::{
+    ffi::OsString,
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// how long the input has to stay the same before suggestions are requested for it.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// suggestions for a web search, from a command that's run in the background. only one command
+/// runs at a time, and only for the latest input.
+#[derive(Debug)]
+pub struct Suggestions {
+    query: String,
+    suggestions: Vec<String>,
+    /// the command for the latest input, and when it was typed.
+    waiting: Option<(Vec<OsString>, Instant)>,
+    /// the input the running command is for, and where its results are sent.
+    running: Option<(String, Receiver<Vec<String>>)>,
+}
+
+impl Suggestions {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            suggestions: Vec::new(),
+            waiting: None,
+            running: None,
+        }
+    }
+
+    /// asks for suggestions for `query` from `argv`, which prints one per line. the command is
+    /// run once the input settles, replacing any request that hasn't started yet.
+    pub fn request(&mut self, query: &str, argv: Vec<OsString>) {
+        self.query = query.to_string();
+        if query.trim().is_empty() {
+            self.suggestions.clear();
+            self.waiting = None;
+        } else {
+            self.waiting = Some((argv, Instant::now()));
+        }
+    }
+
+    /// takes the results of the running command and starts the next one, returning whether the
+    /// suggestions changed.
+    pub fn tick(&mut self) -> bool {
+        let mut changed = false;
+        if let Some((query, receiver)) = &self.running {
+            match receiver.try_recv() {
+                Ok(suggestions) => {
+                    // results for input that's since changed are dropped.
+                    if *query == self.query {
+                        self.suggestions = suggestions;
+                        changed = true;
+                    }
+                    self.running = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.running = None,
+                Err(mpsc::TryRecvError::Empty) => (),
+            }
+        }
+
+        if self.running.is_none()
+            && let Some((_, typed)) = &self.waiting
+            && typed.elapsed() >= DEBOUNCE
+        {
+            let (argv, _) = self.waiting.take().expect("checked above");
+            self.running = spawn(argv).map(|receiver| (self.query.clone(), receiver));
+        }
+        changed
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.running.is_some() || self.waiting.is_some()
+    }
+
+    pub fn suggestions(&self) -> &[String] {
+        &self.suggestions
+    }
+}
+
+fn spawn(argv: Vec<OsString>) -> Option<Receiver<Vec<String>>> {
+    let (program, args) = argv.split_first()?;
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // a failed command just has no suggestions.
+        let suggestions = command
+            .output()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let _ = sender.send(suggestions);
+    });
+    Some(receiver)
+}
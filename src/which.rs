@@ -0,0 +1,15 @@
+use std::{env, fs, os::unix::fs::PermissionsExt, path::Path};
+
+/// whether `program` is a path to an executable, or the name of one on PATH.
+pub fn program_exists(program: &str) -> bool {
+    if program.contains('/') {
+        is_executable(Path::new(program))
+    } else {
+        env::var_os("PATH")
+            .is_some_and(|path| env::split_paths(&path).any(|d| is_executable(&d.join(program))))
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}